@@ -0,0 +1,32 @@
+// build.rs
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Embeds every `locales/<code>.ftl` file in the binary: the supported languages are the available locale files
+fn main() {
+    println!("cargo:rerun-if-changed=locales");
+
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("locales");
+    let mut locales: Vec<(String, String)> = fs::read_dir(&dir)
+        .expect("Unable to read the locales directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ftl"))
+        .map(|path| {
+            let code = path.file_stem().unwrap().to_string_lossy().to_string();
+            (code, path.display().to_string())
+        })
+        .collect();
+    locales.sort();
+
+    let entries: String = locales
+        .iter()
+        .map(|(code, path)| format!("    ({:?}, include_str!({:?})),\n", code, path))
+        .collect();
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("locales.rs");
+    fs::write(
+        out,
+        format!("/// Language code and Fluent source of each locale file\npub const LOCALES: &[(&str, &str)] = &[\n{}];\n", entries),
+    )
+    .expect("Unable to write the locales list");
+}
@@ -0,0 +1,137 @@
+## Nachrichten des Bots, auf Deutsch.
+## Jeder Schlüssel muss in allen Sprachdateien vorhanden sein.
+
+language-flag = 🇩🇪
+language-name = Deutsch
+
+## Schaltflächen
+
+button-cancel = Abbrechen
+button-skip = Überspringen
+button-back = ⬅️ Zurück
+button-reopen = 🔄 Wieder öffnen
+
+## Privater Chat mit dem Kunden
+
+welcome =
+    🤖 Willkommen beim RustBusters Support Bot! 🛠️
+
+    Das sind die verfügbaren Befehle:
+
+    • /support - Ein neues Support-Ticket eröffnen
+      - Wähle deine Sprache
+      - Wähle die Art des Supports
+      - Chatte mit unserer Gruppe
+
+    • /close - Das aktuelle Support-Ticket schließen
+
+    • /reopen - Dein zuletzt geschlossenes Ticket wieder öffnen
+
+    • /language - Deine Sprache ändern
+
+    • /join - Mit dem Einladungscode deinem Team beitreten
+
+    • /redeem - Deinen Lizenzcode einlösen
+
+    Wie können wir dir heute helfen? 😊
+private-chat-only = Dieser Befehl kann nur im privaten Chat verwendet werden
+customer-chat-only = Tickets können nur im privaten Chat mit dem Bot oder in der Gruppe deines Teams eröffnet werden
+choose-language = Bitte wähle deine bevorzugte Sprache für den Support:
+choose-language-preference = Wähle die Sprache des Bots:
+language-changed = Ab jetzt spreche ich Deutsch mit dir.
+ask-team = Zu welchem Team oder welcher Organisation gehörst du? Antworte mit einer Nachricht oder drücke Überspringen.
+choose-category = Welche Art von Support brauchst du?
+category-unknown = Diese Kategorie ist nicht mehr verfügbar, bitte wähle eine andere.
+intake-text-only = Bitte antworte mit einer Textnachricht oder drücke Überspringen.
+intake-reply-hint = Antworte auf diese Nachricht, um die Frage zu beantworten.
+team-code-empty = Schreibe den Einladungscode deines Teams nach dem Befehl: /join CODE
+team-invalid-code = Dieser Einladungscode ist ungültig. Frage dein Team nach dem richtigen.
+team-joined = Du bist dem Team { $team } beigetreten! Jetzt kannst du die offenen Tickets deines Teams sehen und beantworten.
+access-denied = Entschuldigung, der Support ist den Kunden von RustBusters vorbehalten. Wenn du eine Lizenz gekauft hast, sende /redeem mit deinem Lizenzcode.
+access-denied-contact = Entschuldigung, der Support ist den Kunden von RustBusters vorbehalten. Wenn du eine Lizenz gekauft hast, sende /redeem mit deinem Lizenzcode, ansonsten kontaktiere uns unter { $contact }.
+license-code-empty = Schreibe deinen Lizenzcode nach dem Befehl: /redeem CODE
+license-invalid-code = Dieser Lizenzcode ist ungültig oder wurde bereits verwendet.
+license-redeemed = Lizenz aktiviert! Jetzt kannst du mit /support Support-Tickets eröffnen.
+ticket-creation-failed = Entschuldigung, beim Eröffnen des Tickets ist etwas schiefgelaufen. Bitte versuche es mit /support erneut.
+ticket-already-open = Du hast bereits ein offenes Support-Ticket. Schließe es mit /close oder schreibe eine neue Nachricht.
+# $category ist die Bezeichnung der Kategorie, bereits für HTML maskiert
+ticket-created =
+    Support-Ticket für <b><i>{ $category }</i></b> erstellt! Du kannst jetzt über diesen Bot mit RustBusters chatten.
+    Um den Chat zu schließen, verwende /close.
+# $category ist die Bezeichnung der Kategorie, bereits für HTML maskiert
+ticket-created-group =
+    Support-Ticket für <b><i>{ $category }</i></b> erstellt! Erwähne mich oder antworte auf meine Nachrichten, um RustBusters zu schreiben.
+    Um den Chat zu schließen, verwende /close.
+ticket-closed-by-user = Das Support-Ticket wurde geschlossen.
+ticket-closed-by-staff = RustBusters hat den Support-Chat geschlossen. Schreibe /support, um einen neuen zu eröffnen.
+ticket-resolved = RustBusters hat das Ticket als gelöst markiert. Wenn du noch Hilfe brauchst, schreib einfach hier, sonst schließe es mit /close.
+ticket-reopened = Ticket wieder geöffnet! Du kannst über diesen Bot weiter mit RustBusters chatten.
+no-ticket-to-reopen = Es gibt kein kürzlich geschlossenes Ticket zum Wiederöffnen. Schreibe /support, um ein neues zu eröffnen.
+inactivity-warning = In diesem Ticket war es eine Weile still. Wenn niemand schreibt, wird es in { $hours ->
+        [one] einer Stunde
+       *[other] { $hours } Stunden
+    } automatisch geschlossen.
+inactivity-closed = Das Ticket wurde wegen Inaktivität geschlossen. Du kannst es mit /reopen wieder öffnen oder mit /support ein neues eröffnen.
+user-banned = RustBusters hat das Ticket geschlossen. Du kannst den Support-Bot nicht mehr verwenden.
+user-banned-group = RustBusters hat das Ticket geschlossen und { $user } für den Support-Bot gesperrt.
+user-blocked = RustBusters hat dich gesperrt: Du kannst den Support-Bot nicht mehr verwenden.
+user-blocked-group = RustBusters hat { $user } für den Support-Bot gesperrt.
+user-muted = RustBusters hat den Chat pausiert: deine Nachrichten werden bis { $until } nicht zugestellt.
+user-muted-group = RustBusters hat die Nachrichten von { $user } pausiert: sie werden bis { $until } nicht zugestellt.
+user-unbanned = Du kannst den Support-Bot wieder verwenden. Schreibe /support, um ein neues Ticket zu eröffnen.
+rate-limited = Du sendest sehr schnell Nachrichten: Ich leite sie etwas langsamer an RustBusters weiter und fasse die Texte zusammen.
+message-edited = ✏️ (bearbeitet)
+
+## Support-Gruppe
+
+staff-ticket-reopened = Ticket vom Benutzer { $user } wieder geöffnet
+staff-chat-ended-by-user = Chat vom Benutzer { $user } beendet
+staff-chat-ended = Chat beendet
+staff-chat-ended-inactivity = Chat wegen Inaktivität beendet
+staff-inactivity-warning = Seit einer Weile keine Aktivität: der Benutzer wurde gewarnt, das Ticket wird in { $hours ->
+        [one] einer Stunde
+       *[other] { $hours } Stunden
+    } geschlossen.
+staff-unknown-member = Ich kenne dieses Mitglied noch nicht: es muss zuerst in der Gruppe schreiben. Du kannst auch mit /assign auf eine seiner Nachrichten antworten.
+staff-ticket-assigned = Ticket zugewiesen an { $member }
+staff-no-assigned-tickets = Dir sind keine offenen Tickets zugewiesen.
+staff-assigned-tickets = Deine offenen Tickets:
+staff-note-empty = Schreibe die Notiz nach dem Befehl: /note Text
+staff-team-name-empty = Schreibe den Namen des Teams nach dem Befehl: /newteam Name oder /team Name
+staff-team-created = Team { $team } erstellt. Seine Mitglieder können mit /join { $code } beitreten
+staff-team-exists = Das Team { $team } existiert bereits. Seine Mitglieder können mit /join { $code } beitreten
+staff-team-unknown = Es gibt kein Team namens { $team }. Erstelle es mit /newteam { $team }
+staff-team-summary = 👥 Team { $team }, Einladungscode { $code }, { $members ->
+        [one] ein Mitglied
+       *[other] { $members } Mitglieder
+    }
+staff-team-no-tickets = Das Team hat keine offenen Tickets.
+staff-admin-only = Nur die Administratoren der Gruppe können diesen Befehl verwenden.
+staff-allowlist-usage = Schreibe die Benutzer-ID oder den Teamnamen nach dem Befehl: /allow 123456789 oder /revoke team
+staff-allowlist-added = { $name } zur Freigabeliste hinzugefügt
+staff-allowlist-removed = { $name } von der Freigabeliste entfernt
+staff-allowlist-summary = ✅ Freigabeliste: { $users ->
+        [one] ein Kunde
+       *[other] { $users } Kunden
+    }, { $teams ->
+        [one] ein Team
+       *[other] { $teams } Teams
+    }
+staff-allowlist-disabled = Die Freigabeliste ist deaktiviert: alle können Tickets eröffnen.
+staff-license-created = Neuer Lizenzcode: { $code }. Der Kunde aktiviert ihn mit /redeem { $code }
+staff-user-banned = { $user } wurde gesperrt, das Ticket ist geschlossen. Hebe die Sperre mit /unban auf
+staff-member-banned = { $user } wurde gesperrt, das Ticket bleibt für die anderen offen. Hebe die Sperre mit /unban auf
+staff-user-muted = Die Nachrichten von { $user } werden bis { $until } ignoriert. Hebe die Stummschaltung mit /unban auf
+staff-mute-usage = Schreibe nach dem Befehl, wie lange der Benutzer stummgeschaltet wird: /mute 30m, /mute 2h, /mute 1d oder /mute 1w
+staff-unban-usage = Sende /unban im Thema des Benutzers oder schreibe seine Benutzer-ID nach dem Befehl: /unban 123456789
+staff-user-unbanned = { $user } kann den Support-Bot wieder verwenden.
+staff-user-not-blocked = { $user } ist weder gesperrt noch stummgeschaltet.
+staff-profile-name = 👤 { $name }
+staff-profile-team = 👥 Team: { $team }
+staff-profile-language = 🗣 Sprache: { $language }
+staff-profile-history = 📅 Kunde seit { $since }, { $tickets ->
+        [one] erstes Ticket
+       *[other] { $tickets } Tickets
+    }
+staff-intake-summary = 📋 Aufnahmeformular
+staff-intake-skipped = übersprungen
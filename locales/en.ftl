@@ -0,0 +1,137 @@
+## Messages of the bot, in English.
+## Every key must be present in all the locale files.
+
+language-flag = 🇬🇧
+language-name = English
+
+## Buttons
+
+button-cancel = Cancel
+button-skip = Skip
+button-back = ⬅️ Back
+button-reopen = 🔄 Reopen
+
+## Private chat with the customer
+
+welcome =
+    🤖 Welcome to RustBusters Support Bot! 🛠️
+
+    Here are the available commands:
+
+    • /support - Open a new support ticket
+      - Choose your language
+      - Select support type
+      - Chat with our group
+
+    • /close - Close the current support ticket
+
+    • /reopen - Reopen your last closed ticket
+
+    • /language - Change your language
+
+    • /join - Join your team with its invite code
+
+    • /redeem - Redeem your license code
+
+    How can we help you today? 😊
+private-chat-only = This command can only be used in private chat
+customer-chat-only = Tickets can only be opened in the private chat with the bot or in the group of your team
+choose-language = Please select your preferred language for support:
+choose-language-preference = Choose the language of the bot:
+language-changed = From now on I'll talk to you in English.
+ask-team = Which team or organization are you part of? Answer with a message, or press Skip.
+choose-category = What kind of support do you need?
+category-unknown = This category is no longer available, please choose another one.
+intake-text-only = Please answer with a text message, or press Skip.
+intake-reply-hint = Reply to this message to answer.
+team-code-empty = Write the invite code of your team after the command: /join CODE
+team-invalid-code = This invite code is not valid. Ask your team for the right one.
+team-joined = You joined the team { $team }! You can now see and answer the open tickets of your team.
+access-denied = Sorry, the support is reserved to the RustBusters customers. If you bought a license, send /redeem with your license code.
+access-denied-contact = Sorry, the support is reserved to the RustBusters customers. If you bought a license, send /redeem with your license code, otherwise contact us at { $contact }.
+license-code-empty = Write your license code after the command: /redeem CODE
+license-invalid-code = This license code is not valid or was already used.
+license-redeemed = License activated! You can now open support tickets with /support.
+ticket-creation-failed = Sorry, something went wrong while opening the ticket. Please try again with /support.
+ticket-already-open = You already have an open support ticket. Close it with /close or write a new message.
+# $category is the label of the category, already escaped for HTML
+ticket-created =
+    Support ticket created for <b><i>{ $category }</i></b>! You can now chat with RustBusters through this bot.
+    To close the chat, use /close.
+# $category is the label of the category, already escaped for HTML
+ticket-created-group =
+    Support ticket created for <b><i>{ $category }</i></b>! Mention me or reply to my messages to write to RustBusters.
+    To close the chat, use /close.
+ticket-closed-by-user = The support topic has been closed.
+ticket-closed-by-staff = RustBusters closed the support chat. Write /support to open a new one.
+ticket-resolved = RustBusters marked the ticket as resolved. If you still need help just write here, otherwise close it with /close.
+ticket-reopened = Ticket reopened! You can keep chatting with RustBusters through this bot.
+no-ticket-to-reopen = There is no recently closed ticket to reopen. Write /support to open a new one.
+inactivity-warning = This ticket has been quiet for a while. If nobody writes, it will be closed automatically in { $hours ->
+        [one] one hour
+       *[other] { $hours } hours
+    }.
+inactivity-closed = The ticket has been closed for inactivity. You can reopen it with /reopen or open a new one with /support.
+user-banned = RustBusters closed the ticket. You can no longer use the support bot.
+user-banned-group = RustBusters closed the ticket and blocked { $user } from the support bot.
+user-blocked = RustBusters blocked you: you can no longer use the support bot.
+user-blocked-group = RustBusters blocked { $user } from the support bot.
+user-muted = RustBusters paused the chat: your messages won't be delivered until { $until }.
+user-muted-group = RustBusters paused the messages of { $user }: they won't be delivered until { $until }.
+user-unbanned = You can use the support bot again. Write /support to open a new ticket.
+rate-limited = You're sending messages very quickly: I'll pass them on to RustBusters a bit more slowly, grouping the texts together.
+message-edited = ✏️ (edited)
+
+## Support group
+
+staff-ticket-reopened = Ticket reopened by the user { $user }
+staff-chat-ended-by-user = Chat ended by the user { $user }
+staff-chat-ended = Chat ended
+staff-chat-ended-inactivity = Chat ended for inactivity
+staff-inactivity-warning = No activity for a while: the user has been warned, the ticket will be closed in { $hours ->
+        [one] one hour
+       *[other] { $hours } hours
+    }.
+staff-unknown-member = I don't know this member yet: they need to write in the group first. You can also answer one of their messages with /assign.
+staff-ticket-assigned = Ticket assigned to { $member }
+staff-no-assigned-tickets = You have no open tickets assigned.
+staff-assigned-tickets = Your open tickets:
+staff-note-empty = Write the note after the command: /note text
+staff-team-name-empty = Write the name of the team after the command: /newteam name or /team name
+staff-team-created = Team { $team } created. Its members can join with /join { $code }
+staff-team-exists = The team { $team } already exists. Its members can join with /join { $code }
+staff-team-unknown = There is no team named { $team }. Create it with /newteam { $team }
+staff-team-summary = 👥 Team { $team }, invite code { $code }, { $members ->
+        [one] one member
+       *[other] { $members } members
+    }
+staff-team-no-tickets = The team has no open tickets.
+staff-admin-only = Only the administrators of the group can use this command.
+staff-allowlist-usage = Write the user id or the team name after the command: /allow 123456789 or /revoke team
+staff-allowlist-added = { $name } added to the allowlist
+staff-allowlist-removed = { $name } removed from the allowlist
+staff-allowlist-summary = ✅ Allowlist: { $users ->
+        [one] one customer
+       *[other] { $users } customers
+    }, { $teams ->
+        [one] one team
+       *[other] { $teams } teams
+    }
+staff-allowlist-disabled = The allowlist is disabled: everyone can open tickets.
+staff-license-created = New license code: { $code }. The customer activates it with /redeem { $code }
+staff-user-banned = { $user } has been banned, the ticket is closed. Lift the ban with /unban
+staff-member-banned = { $user } has been banned, the ticket stays open for the others. Lift the ban with /unban
+staff-user-muted = The messages of { $user } are ignored until { $until }. Lift the mute with /unban
+staff-mute-usage = Write how long to mute the user after the command: /mute 30m, /mute 2h, /mute 1d or /mute 1w
+staff-unban-usage = Send /unban in the topic of the user, or write their user id after the command: /unban 123456789
+staff-user-unbanned = { $user } can use the support bot again.
+staff-user-not-blocked = { $user } is not banned or muted.
+staff-profile-name = 👤 { $name }
+staff-profile-team = 👥 Team: { $team }
+staff-profile-language = 🗣 Language: { $language }
+staff-profile-history = 📅 Customer since { $since }, { $tickets ->
+        [one] first ticket
+       *[other] { $tickets } tickets
+    }
+staff-intake-summary = 📋 Intake form
+staff-intake-skipped = skipped
@@ -0,0 +1,137 @@
+## Mensajes del bot, en español.
+## Cada clave debe estar presente en todos los archivos de idioma.
+
+language-flag = 🇪🇸
+language-name = Español
+
+## Botones
+
+button-cancel = Cancelar
+button-skip = Omitir
+button-back = ⬅️ Atrás
+button-reopen = 🔄 Reabrir
+
+## Chat privado con el cliente
+
+welcome =
+    🤖 ¡Bienvenido al bot de soporte de RustBusters! 🛠️
+
+    Estos son los comandos disponibles:
+
+    • /support - Abrir un nuevo ticket de soporte
+      - Elige tu idioma
+      - Selecciona el tipo de soporte
+      - Chatea con nuestro grupo
+
+    • /close - Cerrar el ticket de soporte actual
+
+    • /reopen - Reabrir tu último ticket cerrado
+
+    • /language - Cambiar tu idioma
+
+    • /join - Unirte a tu equipo con su código de invitación
+
+    • /redeem - Canjea tu código de licencia
+
+    ¿Cómo podemos ayudarte hoy? 😊
+private-chat-only = Este comando solo se puede usar en el chat privado
+customer-chat-only = Los tickets solo se pueden abrir en el chat privado con el bot o en el grupo de tu equipo
+choose-language = Selecciona tu idioma preferido para el soporte:
+choose-language-preference = Elige el idioma del bot:
+language-changed = A partir de ahora te hablaré en español.
+ask-team = ¿De qué equipo u organización formas parte? Responde con un mensaje, o pulsa Omitir.
+choose-category = ¿Qué tipo de soporte necesitas?
+category-unknown = Esta categoría ya no está disponible, elige otra.
+intake-text-only = Responde con un mensaje de texto, o pulsa Omitir.
+intake-reply-hint = Responde a este mensaje para contestar.
+team-code-empty = Escribe el código de invitación de tu equipo después del comando: /join CÓDIGO
+team-invalid-code = Este código de invitación no es válido. Pide el correcto a tu equipo.
+team-joined = ¡Te has unido al equipo { $team }! Ahora puedes ver y responder los tickets abiertos de tu equipo.
+access-denied = Lo sentimos, el soporte está reservado a los clientes de RustBusters. Si compraste una licencia, envía /redeem con tu código de licencia.
+access-denied-contact = Lo sentimos, el soporte está reservado a los clientes de RustBusters. Si compraste una licencia, envía /redeem con tu código de licencia; si no, contáctanos en { $contact }.
+license-code-empty = Escribe tu código de licencia después del comando: /redeem CÓDIGO
+license-invalid-code = Este código de licencia no es válido o ya se ha usado.
+license-redeemed = ¡Licencia activada! Ahora puedes abrir tickets de soporte con /support.
+ticket-creation-failed = Lo sentimos, algo salió mal al abrir el ticket. Vuelve a intentarlo con /support.
+ticket-already-open = Ya tienes un ticket de soporte abierto. Ciérralo con /close o escribe un nuevo mensaje.
+# $category es la etiqueta de la categoría, ya escapada para HTML
+ticket-created =
+    ¡Ticket de soporte creado para <b><i>{ $category }</i></b>! Ahora puedes chatear con RustBusters a través de este bot.
+    Para cerrar el chat, usa /close.
+# $category es la etiqueta de la categoría, ya escapada para HTML
+ticket-created-group =
+    ¡Ticket de soporte creado para <b><i>{ $category }</i></b>! Mencióname o responde a mis mensajes para escribir a RustBusters.
+    Para cerrar el chat, usa /close.
+ticket-closed-by-user = El ticket de soporte ha sido cerrado.
+ticket-closed-by-staff = RustBusters ha cerrado el chat de soporte. Escribe /support para abrir uno nuevo.
+ticket-resolved = RustBusters ha marcado el ticket como resuelto. Si todavía necesitas ayuda escribe aquí, si no ciérralo con /close.
+ticket-reopened = ¡Ticket reabierto! Puedes seguir chateando con RustBusters a través de este bot.
+no-ticket-to-reopen = No hay ningún ticket cerrado recientemente para reabrir. Escribe /support para abrir uno nuevo.
+inactivity-warning = Este ticket lleva un tiempo sin actividad. Si nadie escribe, se cerrará automáticamente en { $hours ->
+        [one] una hora
+       *[other] { $hours } horas
+    }.
+inactivity-closed = El ticket se ha cerrado por inactividad. Puedes reabrirlo con /reopen o abrir uno nuevo con /support.
+user-banned = RustBusters cerró el ticket. Ya no puedes usar el bot de soporte.
+user-banned-group = RustBusters cerró el ticket y bloqueó a { $user } en el bot de soporte.
+user-blocked = RustBusters te bloqueó: ya no puedes usar el bot de soporte.
+user-blocked-group = RustBusters bloqueó a { $user } en el bot de soporte.
+user-muted = RustBusters pausó el chat: tus mensajes no se entregarán hasta { $until }.
+user-muted-group = RustBusters pausó los mensajes de { $user }: no se entregarán hasta { $until }.
+user-unbanned = Puedes volver a usar el bot de soporte. Escribe /support para abrir un nuevo ticket.
+rate-limited = Estás enviando mensajes muy rápido: los pasaré a RustBusters un poco más despacio, agrupando los textos.
+message-edited = ✏️ (editado)
+
+## Grupo de soporte
+
+staff-ticket-reopened = Ticket reabierto por el usuario { $user }
+staff-chat-ended-by-user = Chat terminado por el usuario { $user }
+staff-chat-ended = Chat terminado
+staff-chat-ended-inactivity = Chat terminado por inactividad
+staff-inactivity-warning = Sin actividad desde hace un tiempo: se ha avisado al usuario, el ticket se cerrará en { $hours ->
+        [one] una hora
+       *[other] { $hours } horas
+    }.
+staff-unknown-member = Todavía no conozco a este miembro: primero tiene que escribir en el grupo. También puedes responder a uno de sus mensajes con /assign.
+staff-ticket-assigned = Ticket asignado a { $member }
+staff-no-assigned-tickets = No tienes tickets abiertos asignados.
+staff-assigned-tickets = Tus tickets abiertos:
+staff-note-empty = Escribe la nota después del comando: /note texto
+staff-team-name-empty = Escribe el nombre del equipo después del comando: /newteam nombre o /team nombre
+staff-team-created = Equipo { $team } creado. Sus miembros pueden unirse con /join { $code }
+staff-team-exists = El equipo { $team } ya existe. Sus miembros pueden unirse con /join { $code }
+staff-team-unknown = No hay ningún equipo llamado { $team }. Créalo con /newteam { $team }
+staff-team-summary = 👥 Equipo { $team }, código de invitación { $code }, { $members ->
+        [one] un miembro
+       *[other] { $members } miembros
+    }
+staff-team-no-tickets = El equipo no tiene tickets abiertos.
+staff-admin-only = Solo los administradores del grupo pueden usar este comando.
+staff-allowlist-usage = Escribe el id del usuario o el nombre del equipo después del comando: /allow 123456789 o /revoke equipo
+staff-allowlist-added = { $name } añadido a la lista de clientes autorizados
+staff-allowlist-removed = { $name } eliminado de la lista de clientes autorizados
+staff-allowlist-summary = ✅ Clientes autorizados: { $users ->
+        [one] un cliente
+       *[other] { $users } clientes
+    }, { $teams ->
+        [one] un equipo
+       *[other] { $teams } equipos
+    }
+staff-allowlist-disabled = La lista de clientes autorizados está desactivada: cualquiera puede abrir tickets.
+staff-license-created = Nuevo código de licencia: { $code }. El cliente lo activa con /redeem { $code }
+staff-user-banned = { $user } ha sido bloqueado, el ticket está cerrado. Quita el bloqueo con /unban
+staff-member-banned = { $user } ha sido bloqueado, el ticket sigue abierto para los demás. Quita el bloqueo con /unban
+staff-user-muted = Los mensajes de { $user } se ignoran hasta { $until }. Quita el silencio con /unban
+staff-mute-usage = Escribe cuánto tiempo silenciar al usuario después del comando: /mute 30m, /mute 2h, /mute 1d o /mute 1w
+staff-unban-usage = Envía /unban en el tema del usuario, o escribe su id después del comando: /unban 123456789
+staff-user-unbanned = { $user } puede volver a usar el bot de soporte.
+staff-user-not-blocked = { $user } no está bloqueado ni silenciado.
+staff-profile-name = 👤 { $name }
+staff-profile-team = 👥 Equipo: { $team }
+staff-profile-language = 🗣 Idioma: { $language }
+staff-profile-history = 📅 Cliente desde { $since }, { $tickets ->
+        [one] primer ticket
+       *[other] { $tickets } tickets
+    }
+staff-intake-summary = 📋 Formulario inicial
+staff-intake-skipped = omitida
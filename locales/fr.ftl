@@ -0,0 +1,137 @@
+## Messages du bot, en français.
+## Chaque clé doit être présente dans tous les fichiers de langue.
+
+language-flag = 🇫🇷
+language-name = Français
+
+## Boutons
+
+button-cancel = Annuler
+button-skip = Passer
+button-back = ⬅️ Retour
+button-reopen = 🔄 Rouvrir
+
+## Chat privé avec le client
+
+welcome =
+    🤖 Bienvenue sur le bot de support RustBusters ! 🛠️
+
+    Voici les commandes disponibles :
+
+    • /support - Ouvrir un nouveau ticket de support
+      - Choisis ta langue
+      - Sélectionne le type de support
+      - Discute avec notre groupe
+
+    • /close - Fermer le ticket de support en cours
+
+    • /reopen - Rouvrir ton dernier ticket fermé
+
+    • /language - Changer ta langue
+
+    • /join - Rejoindre ton équipe avec son code d'invitation
+
+    • /redeem - Activer ton code de licence
+
+    Comment pouvons-nous t'aider aujourd'hui ? 😊
+private-chat-only = Cette commande ne peut être utilisée que dans le chat privé
+customer-chat-only = Les tickets ne peuvent être ouverts que dans le chat privé avec le bot ou dans le groupe de ton équipe
+choose-language = Sélectionne ta langue préférée pour le support :
+choose-language-preference = Choisis la langue du bot :
+language-changed = Désormais je te parlerai en français.
+ask-team = De quelle équipe ou organisation fais-tu partie ? Réponds avec un message, ou appuie sur Passer.
+choose-category = De quel type de support as-tu besoin ?
+category-unknown = Cette catégorie n'est plus disponible, choisis-en une autre.
+intake-text-only = Réponds avec un message texte, ou appuie sur Passer.
+intake-reply-hint = Réponds à ce message pour répondre à la question.
+team-code-empty = Écris le code d'invitation de ton équipe après la commande : /join CODE
+team-invalid-code = Ce code d'invitation n'est pas valide. Demande le bon à ton équipe.
+team-joined = Tu as rejoint l'équipe { $team } ! Tu peux maintenant voir et répondre aux tickets ouverts de ton équipe.
+access-denied = Désolé, le support est réservé aux clients de RustBusters. Si tu as acheté une licence, envoie /redeem avec ton code de licence.
+access-denied-contact = Désolé, le support est réservé aux clients de RustBusters. Si tu as acheté une licence, envoie /redeem avec ton code de licence, sinon contacte-nous à { $contact }.
+license-code-empty = Écris ton code de licence après la commande : /redeem CODE
+license-invalid-code = Ce code de licence n'est pas valide ou a déjà été utilisé.
+license-redeemed = Licence activée ! Tu peux maintenant ouvrir des tickets de support avec /support.
+ticket-creation-failed = Désolé, un problème est survenu lors de l'ouverture du ticket. Réessaie avec /support.
+ticket-already-open = Tu as déjà un ticket de support ouvert. Ferme-le avec /close ou écris un nouveau message.
+# $category est le libellé de la catégorie, déjà échappé pour le HTML
+ticket-created =
+    Ticket de support créé pour <b><i>{ $category }</i></b> ! Tu peux maintenant discuter avec RustBusters via ce bot.
+    Pour fermer le chat, utilise /close.
+# $category est le libellé de la catégorie, déjà échappé pour le HTML
+ticket-created-group =
+    Ticket de support créé pour <b><i>{ $category }</i></b> ! Mentionne-moi ou réponds à mes messages pour écrire à RustBusters.
+    Pour fermer le chat, utilise /close.
+ticket-closed-by-user = Le ticket de support a été fermé.
+ticket-closed-by-staff = RustBusters a fermé le chat de support. Écris /support pour en ouvrir un nouveau.
+ticket-resolved = RustBusters a marqué le ticket comme résolu. Si tu as encore besoin d'aide écris ici, sinon ferme-le avec /close.
+ticket-reopened = Ticket rouvert ! Tu peux continuer à discuter avec RustBusters via ce bot.
+no-ticket-to-reopen = Il n'y a aucun ticket fermé récemment à rouvrir. Écris /support pour en ouvrir un nouveau.
+inactivity-warning = Ce ticket est calme depuis un moment. Si personne n'écrit, il sera fermé automatiquement dans { $hours ->
+        [one] une heure
+       *[other] { $hours } heures
+    }.
+inactivity-closed = Le ticket a été fermé pour inactivité. Tu peux le rouvrir avec /reopen ou en ouvrir un nouveau avec /support.
+user-banned = RustBusters a fermé le ticket. Tu ne peux plus utiliser le bot de support.
+user-banned-group = RustBusters a fermé le ticket et bloqué { $user } sur le bot de support.
+user-blocked = RustBusters t'a bloqué : tu ne peux plus utiliser le bot de support.
+user-blocked-group = RustBusters a bloqué { $user } sur le bot de support.
+user-muted = RustBusters a mis le chat en pause : tes messages ne seront pas transmis avant { $until }.
+user-muted-group = RustBusters a mis en pause les messages de { $user } : ils ne seront pas transmis avant { $until }.
+user-unbanned = Tu peux de nouveau utiliser le bot de support. Écris /support pour ouvrir un nouveau ticket.
+rate-limited = Tu envoies des messages très rapidement : je vais les transmettre à RustBusters un peu plus lentement, en regroupant les textes.
+message-edited = ✏️ (modifié)
+
+## Groupe de support
+
+staff-ticket-reopened = Ticket rouvert par l'utilisateur { $user }
+staff-chat-ended-by-user = Chat terminé par l'utilisateur { $user }
+staff-chat-ended = Chat terminé
+staff-chat-ended-inactivity = Chat terminé pour inactivité
+staff-inactivity-warning = Aucune activité depuis un moment : l'utilisateur a été prévenu, le ticket sera fermé dans { $hours ->
+        [one] une heure
+       *[other] { $hours } heures
+    }.
+staff-unknown-member = Je ne connais pas encore ce membre : il doit d'abord écrire dans le groupe. Tu peux aussi répondre à l'un de ses messages avec /assign.
+staff-ticket-assigned = Ticket assigné à { $member }
+staff-no-assigned-tickets = Tu n'as aucun ticket ouvert assigné.
+staff-assigned-tickets = Tes tickets ouverts :
+staff-note-empty = Écris la note après la commande : /note texte
+staff-team-name-empty = Écris le nom de l'équipe après la commande : /newteam nom ou /team nom
+staff-team-created = Équipe { $team } créée. Ses membres peuvent la rejoindre avec /join { $code }
+staff-team-exists = L'équipe { $team } existe déjà. Ses membres peuvent la rejoindre avec /join { $code }
+staff-team-unknown = Il n'y a aucune équipe nommée { $team }. Crée-la avec /newteam { $team }
+staff-team-summary = 👥 Équipe { $team }, code d'invitation { $code }, { $members ->
+        [one] un membre
+       *[other] { $members } membres
+    }
+staff-team-no-tickets = L'équipe n'a aucun ticket ouvert.
+staff-admin-only = Seuls les administrateurs du groupe peuvent utiliser cette commande.
+staff-allowlist-usage = Écris l'id de l'utilisateur ou le nom de l'équipe après la commande : /allow 123456789 ou /revoke équipe
+staff-allowlist-added = { $name } ajouté à la liste des clients autorisés
+staff-allowlist-removed = { $name } retiré de la liste des clients autorisés
+staff-allowlist-summary = ✅ Clients autorisés : { $users ->
+        [one] un client
+       *[other] { $users } clients
+    }, { $teams ->
+        [one] une équipe
+       *[other] { $teams } équipes
+    }
+staff-allowlist-disabled = La liste des clients autorisés est désactivée : tout le monde peut ouvrir des tickets.
+staff-license-created = Nouveau code de licence : { $code }. Le client l'active avec /redeem { $code }
+staff-user-banned = { $user } a été banni, le ticket est fermé. Lève le bannissement avec /unban
+staff-member-banned = { $user } a été banni, le ticket reste ouvert pour les autres. Lève le bannissement avec /unban
+staff-user-muted = Les messages de { $user } sont ignorés jusqu'à { $until }. Lève la mise en sourdine avec /unban
+staff-mute-usage = Écris pendant combien de temps rendre l'utilisateur muet après la commande : /mute 30m, /mute 2h, /mute 1d ou /mute 1w
+staff-unban-usage = Envoie /unban dans le sujet de l'utilisateur, ou écris son id après la commande : /unban 123456789
+staff-user-unbanned = { $user } peut de nouveau utiliser le bot de support.
+staff-user-not-blocked = { $user } n'est ni banni ni muet.
+staff-profile-name = 👤 { $name }
+staff-profile-team = 👥 Équipe : { $team }
+staff-profile-language = 🗣 Langue : { $language }
+staff-profile-history = 📅 Client depuis le { $since }, { $tickets ->
+        [one] premier ticket
+       *[other] { $tickets } tickets
+    }
+staff-intake-summary = 📋 Formulaire initial
+staff-intake-skipped = ignorée
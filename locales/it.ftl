@@ -0,0 +1,137 @@
+## Messaggi del bot, in italiano.
+## Ogni chiave deve essere presente in tutti i file di traduzione.
+
+language-flag = 🇮🇹
+language-name = Italiano
+
+## Pulsanti
+
+button-cancel = Annulla
+button-skip = Salta
+button-back = ⬅️ Indietro
+button-reopen = 🔄 Riapri
+
+## Chat privata con il cliente
+
+welcome =
+    🤖 Benvenuto nel bot di supporto di RustBusters! 🛠️
+
+    Ecco i comandi disponibili:
+
+    • /support - Apri un nuovo ticket di supporto
+      - Scegli la lingua
+      - Seleziona il tipo di supporto
+      - Chatta con il nostro gruppo
+
+    • /close - Chiudi il ticket di supporto in corso
+
+    • /reopen - Riapri l'ultimo ticket chiuso
+
+    • /language - Cambia la lingua
+
+    • /join - Entra nel tuo team con il suo codice di invito
+
+    • /redeem - Attiva il tuo codice di licenza
+
+    Come possiamo aiutarti oggi? 😊
+private-chat-only = Questo comando può essere usato solo in chat privata
+customer-chat-only = I ticket si possono aprire solo nella chat privata con il bot o nel gruppo del tuo team
+choose-language = Seleziona la lingua che preferisci per il supporto:
+choose-language-preference = Scegli la lingua del bot:
+language-changed = D'ora in poi ti parlerò in italiano.
+ask-team = Di quale team o organizzazione fai parte? Rispondi con un messaggio, oppure premi Salta.
+choose-category = Che tipo di supporto ti serve?
+category-unknown = Questa categoria non è più disponibile, scegline un'altra.
+intake-text-only = Rispondi con un messaggio di testo, oppure premi Salta.
+intake-reply-hint = Rispondi a questo messaggio per dare la tua risposta.
+team-code-empty = Scrivi il codice di invito del tuo team dopo il comando: /join CODICE
+team-invalid-code = Questo codice di invito non è valido. Chiedi al tuo team quello giusto.
+team-joined = Sei entrato nel team { $team }! Ora puoi vedere e rispondere ai ticket aperti del tuo team.
+access-denied = Ci dispiace, il supporto è riservato ai clienti di RustBusters. Se hai acquistato una licenza, invia /redeem con il tuo codice di licenza.
+access-denied-contact = Ci dispiace, il supporto è riservato ai clienti di RustBusters. Se hai acquistato una licenza, invia /redeem con il tuo codice di licenza, altrimenti contattaci a { $contact }.
+license-code-empty = Scrivi il tuo codice di licenza dopo il comando: /redeem CODICE
+license-invalid-code = Questo codice di licenza non è valido o è già stato usato.
+license-redeemed = Licenza attivata! Ora puoi aprire ticket di supporto con /support.
+ticket-creation-failed = Ci dispiace, qualcosa è andato storto nell'apertura del ticket. Riprova con /support.
+ticket-already-open = Hai già un ticket di supporto aperto. Chiudilo con /close o scrivi un nuovo messaggio.
+# $category è l'etichetta della categoria, già convertita per l'HTML
+ticket-created =
+    Ticket di supporto creato per <b><i>{ $category }</i></b>! Puoi ora chattare con RustBusters attraverso questo bot.
+    Per chiudere la chat, usa /close.
+# $category è l'etichetta della categoria, già convertita per l'HTML
+ticket-created-group =
+    Ticket di supporto creato per <b><i>{ $category }</i></b>! Menzionami o rispondi ai miei messaggi per scrivere a RustBusters.
+    Per chiudere la chat, usa /close.
+ticket-closed-by-user = Il ticket di supporto è stato chiuso.
+ticket-closed-by-staff = RustBusters ha chiuso la chat di supporto. Scrivi /support per aprirne una nuova.
+ticket-resolved = RustBusters ha segnato il ticket come risolto. Se hai ancora bisogno di aiuto scrivi qui, altrimenti chiudilo con /close.
+ticket-reopened = Ticket riaperto! Puoi continuare a chattare con RustBusters attraverso questo bot.
+no-ticket-to-reopen = Non c'è nessun ticket chiuso di recente da riaprire. Scrivi /support per aprirne uno nuovo.
+inactivity-warning = Non riceviamo messaggi su questo ticket da un po'. Se non scrivi niente, verrà chiuso automaticamente tra { $hours ->
+        [one] un'ora
+       *[other] { $hours } ore
+    }.
+inactivity-closed = Il ticket è stato chiuso per inattività. Puoi riaprirlo con /reopen o aprirne uno nuovo con /support.
+user-banned = RustBusters ha chiuso il ticket. Non puoi più usare il bot di supporto.
+user-banned-group = RustBusters ha chiuso il ticket e bloccato { $user } sul bot di supporto.
+user-blocked = RustBusters ti ha bloccato: non puoi più usare il bot di supporto.
+user-blocked-group = RustBusters ha bloccato { $user } sul bot di supporto.
+user-muted = RustBusters ha messo in pausa la chat: i tuoi messaggi non verranno consegnati fino a { $until }.
+user-muted-group = RustBusters ha messo in pausa i messaggi di { $user }: non verranno consegnati fino a { $until }.
+user-unbanned = Puoi di nuovo usare il bot di supporto. Scrivi /support per aprire un nuovo ticket.
+rate-limited = Stai inviando messaggi molto velocemente: li inoltrerò a RustBusters un po' più lentamente, raggruppando i testi.
+message-edited = ✏️ (modificato)
+
+## Gruppo di supporto
+
+staff-ticket-reopened = Ticket riaperto dall'utente { $user }
+staff-chat-ended-by-user = Chat chiusa dall'utente { $user }
+staff-chat-ended = Chat chiusa
+staff-chat-ended-inactivity = Chat chiusa per inattività
+staff-inactivity-warning = Nessuna attività da un po': l'utente è stato avvisato, il ticket verrà chiuso tra { $hours ->
+        [one] un'ora
+       *[other] { $hours } ore
+    }.
+staff-unknown-member = Non conosco ancora questo membro: deve prima scrivere nel gruppo. Puoi anche rispondere a uno dei suoi messaggi con /assign.
+staff-ticket-assigned = Ticket assegnato a { $member }
+staff-no-assigned-tickets = Non hai ticket aperti assegnati.
+staff-assigned-tickets = I tuoi ticket aperti:
+staff-note-empty = Scrivi la nota dopo il comando: /note testo
+staff-team-name-empty = Scrivi il nome del team dopo il comando: /newteam nome o /team nome
+staff-team-created = Team { $team } creato. I suoi membri possono entrare con /join { $code }
+staff-team-exists = Il team { $team } esiste già. I suoi membri possono entrare con /join { $code }
+staff-team-unknown = Non c'è nessun team chiamato { $team }. Crealo con /newteam { $team }
+staff-team-summary = 👥 Team { $team }, codice di invito { $code }, { $members ->
+        [one] un membro
+       *[other] { $members } membri
+    }
+staff-team-no-tickets = Il team non ha ticket aperti.
+staff-admin-only = Solo gli amministratori del gruppo possono usare questo comando.
+staff-allowlist-usage = Scrivi l'id dell'utente o il nome del team dopo il comando: /allow 123456789 o /revoke team
+staff-allowlist-added = { $name } aggiunto alla lista dei clienti abilitati
+staff-allowlist-removed = { $name } rimosso dalla lista dei clienti abilitati
+staff-allowlist-summary = ✅ Clienti abilitati: { $users ->
+        [one] un cliente
+       *[other] { $users } clienti
+    }, { $teams ->
+        [one] un team
+       *[other] { $teams } team
+    }
+staff-allowlist-disabled = La lista dei clienti abilitati è disattivata: chiunque può aprire ticket.
+staff-license-created = Nuovo codice di licenza: { $code }. Il cliente lo attiva con /redeem { $code }
+staff-user-banned = { $user } è stato bannato, il ticket è chiuso. Togli il ban con /unban
+staff-member-banned = { $user } è stato bannato, il ticket resta aperto per gli altri. Togli il ban con /unban
+staff-user-muted = I messaggi di { $user } vengono ignorati fino a { $until }. Togli il silenziamento con /unban
+staff-mute-usage = Scrivi per quanto silenziare l'utente dopo il comando: /mute 30m, /mute 2h, /mute 1d o /mute 1w
+staff-unban-usage = Invia /unban nel topic dell'utente, o scrivi il suo id dopo il comando: /unban 123456789
+staff-user-unbanned = { $user } può di nuovo usare il bot di supporto.
+staff-user-not-blocked = { $user } non è bannato né silenziato.
+staff-profile-name = 👤 { $name }
+staff-profile-team = 👥 Team: { $team }
+staff-profile-language = 🗣 Lingua: { $language }
+staff-profile-history = 📅 Cliente dal { $since }, { $tickets ->
+        [one] primo ticket
+       *[other] { $tickets } ticket
+    }
+staff-intake-summary = 📋 Modulo iniziale
+staff-intake-skipped = saltata
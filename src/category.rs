@@ -0,0 +1,152 @@
+// category.rs
+use crate::config::config;
+use crate::i18n::tr;
+use crate::state::Language;
+use crate::util::{get_random_topic_color, TOPIC_COLORS};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::OnceLock;
+use teloxide::types::Rgb;
+use teloxide::utils::html;
+
+/// Categories used when no categories file is configured
+const DEFAULT_CATEGORIES: &str = include_str!("../categories.json");
+
+/// A question of the intake form of a category
+#[derive(Deserialize)]
+pub struct IntakeQuestion {
+    /// Short title of the answer in the summary posted in the topic
+    pub label: String,
+    /// Text of the question, by language code
+    #[serde(flatten)]
+    texts: HashMap<String, String>,
+}
+
+impl IntakeQuestion {
+    /// Text of the question in the language, in English if it wasn't translated
+    pub fn text(&self, language: Language) -> &str {
+        self.texts
+            .get(language.code())
+            .or_else(|| self.texts.get(Language::DEFAULT.code()))
+            .unwrap_or(&self.label)
+    }
+}
+
+/// A category of support tickets, chosen by the user when opening a ticket
+#[derive(Deserialize)]
+pub struct Category {
+    /// Identifier stored on the tickets, it must never change
+    pub id: String,
+    /// Name shown to the support group, in the topic names
+    pub name: String,
+    pub emoji: String,
+    /// Label of the keyboard button, by language code
+    labels: HashMap<String, String>,
+    /// Topic icon color as hex RGB, one of the colors allowed by Telegram (random if missing)
+    color: Option<String>,
+    /// Questions asked to the user before the topic is created
+    #[serde(default)]
+    pub questions: Vec<IntakeQuestion>,
+    /// Support group receiving the new tickets of the category when no route of the routing table matches them
+    #[serde(default)]
+    pub route: Option<i64>,
+}
+
+impl Category {
+    /// Label of the category for the user, in English if it wasn't translated
+    pub fn label(&self, language: Language) -> &str {
+        self.labels
+            .get(language.code())
+            .or_else(|| self.labels.get(Language::DEFAULT.code()))
+            .unwrap_or(&self.name)
+    }
+
+    /// Icon color of the topics of the category
+    pub fn topic_color(&self) -> Rgb {
+        match self.color.as_deref().map(parse_color) {
+            Some(Some(color)) => Rgb::from_u32(color),
+            _ => get_random_topic_color(),
+        }
+    }
+}
+
+fn parse_color(color: &str) -> Option<u32> {
+    u32::from_str_radix(color.trim_start_matches('#'), 16).ok()
+}
+
+/// Checks the categories on startup, so a broken file is noticed right away
+fn validate(categories: &[Category]) {
+    assert!(!categories.is_empty(), "At least one category is needed");
+
+    let mut ids = HashSet::new();
+    for category in categories {
+        assert!(
+            ids.insert(&category.id),
+            "Duplicate category {}",
+            category.id
+        );
+        // The id is part of the callback data, limited to 64 bytes
+        assert!(
+            !category.id.is_empty() && category.id.len() <= 32,
+            "Invalid category id {:?}",
+            category.id
+        );
+        if let Some(color) = &category.color {
+            assert!(
+                parse_color(color).is_some_and(|c| TOPIC_COLORS.contains(&c)),
+                "Invalid topic color {} of category {}: it must be one of the colors allowed by Telegram",
+                color,
+                category.id
+            );
+        }
+        assert!(
+            category.route.is_none_or(|group| group < 0),
+            "Invalid route of category {}: it must be the id of a group",
+            category.id
+        );
+    }
+}
+
+/// Configured ticket categories, in the order of the keyboard
+pub fn categories() -> &'static [Category] {
+    static CATEGORIES: OnceLock<Vec<Category>> = OnceLock::new();
+    CATEGORIES.get_or_init(|| {
+        let path = &config().categories_path;
+        let json = if path.exists() {
+            fs::read_to_string(path).expect("Unable to read the categories")
+        } else {
+            DEFAULT_CATEGORIES.to_string()
+        };
+        let categories: Vec<Category> = serde_json::from_str(&json).expect("Invalid categories");
+        validate(&categories);
+        categories
+    })
+}
+
+/// Returns the configured category with the id
+pub fn find_category(id: &str) -> Option<&'static Category> {
+    categories().iter().find(|category| category.id == id)
+}
+
+/// Name of the category shown to the support group, falling back to the id for categories no longer configured
+pub fn category_name(id: &str) -> String {
+    match find_category(id) {
+        Some(category) => format!("{} {}", category.emoji, category.name),
+        None => id.to_string(),
+    }
+}
+
+/// Summary of the answers to the intake form, in HTML, posted at the top of the topic
+pub fn intake_summary(category: &Category, answers: &[Option<String>]) -> String {
+    let language = config().staff_language;
+    let mut summary = html::bold(&tr!(language, "staff-intake-summary"));
+    for (question, answer) in category.questions.iter().zip(answers) {
+        let answer = match answer {
+            Some(answer) => html::escape(answer),
+            None => html::italic(&tr!(language, "staff-intake-skipped")),
+        };
+        summary.push_str(&format!("\n\n{}\n{}", html::bold(&question.label), answer));
+    }
+    summary
+}
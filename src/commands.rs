@@ -12,4 +12,40 @@ pub enum Command {
     Support,
     /// Close the current support ticket
     Close,
+    /// Reopen your last closed support ticket
+    Reopen,
+    /// Change your preferred language
+    Language,
+    /// Join your team with its invite code: /join CODE
+    Join(String),
+    /// Redeem your license code to open tickets: /redeem CODE
+    Redeem(String),
+    /// Mark the ticket of the topic as resolved (support group only)
+    Resolve,
+    /// Take charge of the ticket of the topic (support group only)
+    Claim,
+    /// Assign the ticket of the topic to a member: /assign @member, or reply to one of their messages (support group only)
+    Assign(String),
+    /// List the open tickets assigned to you (support group only)
+    Mine,
+    /// Add an internal note to the ticket of the topic, never sent to the user (support group only)
+    Note(String),
+    /// Create a team of customers and get its invite code: /newteam name (support group only)
+    NewTeam(String),
+    /// List the open tickets of a team: /team name (support group only)
+    Team(String),
+    /// Let a customer or a team open tickets: /allow user_id or /allow team (support group admins only)
+    Allow(String),
+    /// Remove a customer or a team from the allowlist: /revoke user_id or /revoke team (support group admins only)
+    Revoke(String),
+    /// List the customers and the teams in the allowlist (support group admins only)
+    Allowlist,
+    /// Create a license code for a new customer (support group admins only)
+    License,
+    /// Ban the customer of the topic and close their ticket (support group only)
+    Ban,
+    /// Ignore the messages of the customer of the topic for a while: /mute 30m, 2h, 1d or 1w (support group only)
+    Mute(String),
+    /// Lift the ban or the mute of the customer of the topic, or of a user id: /unban user_id (support group only)
+    Unban(String),
 }
@@ -0,0 +1,154 @@
+// config.rs
+use crate::ratelimit::Limit;
+use crate::state::Language;
+use chrono::TimeDelta;
+use std::env;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Backend used to persist the tickets
+pub enum StorageBackend {
+    /// Keeps everything in memory, nothing survives a restart (useful for tests)
+    Memory,
+    /// JSON file, rewritten atomically on every change
+    Json(PathBuf),
+    /// SQLite database
+    Sqlite(PathBuf),
+}
+
+impl StorageBackend {
+    /// Whether the data survives a restart
+    pub fn is_persistent(&self) -> bool {
+        !matches!(self, StorageBackend::Memory)
+    }
+}
+
+/// Configuration of the bot, read from the environment
+pub struct Config {
+    pub storage: StorageBackend,
+    /// `bindings.json` saved by the previous versions of the bot, imported on startup
+    pub legacy_bindings_path: PathBuf,
+    /// How long after closing a ticket the user can still reopen it
+    pub reopen_window: TimeDelta,
+    /// Time without messages after which the user is warned that the ticket will be closed (zero disables it)
+    pub inactivity_warning: TimeDelta,
+    /// Time after the warning after which the inactive ticket is closed
+    pub inactivity_grace: TimeDelta,
+    /// How often the inactive tickets are checked
+    pub inactivity_check_interval: TimeDelta,
+    /// Messages of the support group starting with this prefix are kept as internal notes (empty disables it)
+    pub note_prefix: String,
+    /// How long to wait for all the items of an album before relaying it
+    pub media_group_wait: TimeDelta,
+    /// JSON file with the ticket categories, the built-in ones are used if missing
+    pub categories_path: PathBuf,
+    /// JSON file with the routing table of the tickets to the support groups, everything goes to `SUPPORT_GROUP` if missing
+    pub routes_path: PathBuf,
+    /// Language of the messages of the bot in the support groups
+    pub staff_language: Language,
+    /// Only the customers in the allowlist, or in an allowed team, can open tickets
+    pub allowlist_enabled: bool,
+    /// How the customers outside the allowlist can contact RustBusters, shown when they are refused
+    pub sales_contact: Option<String>,
+    /// Rate limit of the messages of each customer
+    pub user_rate_limit: Limit,
+    /// Rate limit of the messages relayed to each support group
+    pub group_rate_limit: Limit,
+}
+
+impl Config {
+    fn from_env() -> Self {
+        let path = env::var("STORAGE_PATH").ok().map(PathBuf::from);
+        let storage = match env::var("STORAGE").as_deref() {
+            Ok("memory") => StorageBackend::Memory,
+            Ok("json") => {
+                StorageBackend::Json(path.unwrap_or_else(|| PathBuf::from("/data/tickets.json")))
+            }
+            Ok("sqlite") | Err(_) => {
+                StorageBackend::Sqlite(path.unwrap_or_else(|| PathBuf::from("/data/support.db")))
+            }
+            Ok(other) => panic!("Unknown STORAGE backend: {}", other),
+        };
+
+        Self {
+            storage,
+            legacy_bindings_path: env::var("LEGACY_BINDINGS_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("/data/bindings.json")),
+            reopen_window: TimeDelta::hours(env_non_negative("REOPEN_WINDOW_HOURS", 48).into()),
+            inactivity_warning: TimeDelta::hours(
+                env_non_negative("INACTIVITY_WARNING_HOURS", 72).into(),
+            ),
+            inactivity_grace: TimeDelta::hours(
+                env_non_negative("INACTIVITY_GRACE_HOURS", 24).into(),
+            ),
+            inactivity_check_interval: TimeDelta::minutes(
+                env_positive("INACTIVITY_CHECK_MINUTES", 15).into(),
+            ),
+            note_prefix: env::var("NOTE_PREFIX").unwrap_or_else(|_| "//".to_string()),
+            media_group_wait: TimeDelta::milliseconds(
+                env_non_negative("MEDIA_GROUP_WAIT_MS", 1000).into(),
+            ),
+            categories_path: env::var("CATEGORIES_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("/data/categories.json")),
+            routes_path: env::var("ROUTES_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("/data/routes.json")),
+            staff_language: match env::var("STAFF_LANGUAGE") {
+                Ok(code) => Language::from_code(&code)
+                    .unwrap_or_else(|| panic!("Unknown STAFF_LANGUAGE: {}", code)),
+                Err(_) => Language::DEFAULT,
+            },
+            allowlist_enabled: env_flag("ALLOWLIST_ENABLED"),
+            sales_contact: env::var("SALES_CONTACT").ok().filter(|c| !c.is_empty()),
+            user_rate_limit: Limit {
+                burst: env_positive("USER_RATE_LIMIT_BURST", 10),
+                per_minute: env_positive("USER_RATE_LIMIT_PER_MINUTE", 20),
+            },
+            group_rate_limit: Limit {
+                burst: env_positive("GROUP_RATE_LIMIT_BURST", 20),
+                per_minute: env_positive("GROUP_RATE_LIMIT_PER_MINUTE", 20),
+            },
+        }
+    }
+}
+
+/// Reads a numeric environment variable, using `default` when it is not set
+fn env_number(name: &str, default: i64) -> i64 {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number.", name)),
+        Err(_) => default,
+    }
+}
+
+/// Reads a positive numeric environment variable, using `default` when it is not set
+fn env_positive(name: &str, default: u32) -> u32 {
+    u32::try_from(env_number(name, default.into()))
+        .ok()
+        .filter(|value| *value > 0)
+        .unwrap_or_else(|| panic!("{} must be a positive number.", name))
+}
+
+/// Reads a numeric environment variable that can't be negative, using `default` when it is not set
+fn env_non_negative(name: &str, default: u32) -> u32 {
+    u32::try_from(env_number(name, default.into()))
+        .unwrap_or_else(|_| panic!("{} must be zero or a positive number.", name))
+}
+
+/// Reads a boolean environment variable, false when it is not set
+fn env_flag(name: &str) -> bool {
+    match env::var(name).as_deref() {
+        Ok("1" | "true" | "yes") => true,
+        Ok("0" | "false" | "no" | "") | Err(_) => false,
+        Ok(other) => panic!("{} must be true or false, not {}.", name, other),
+    }
+}
+
+/// Global configuration
+pub fn config() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(Config::from_env)
+}
@@ -1,271 +1,2210 @@
 // handlers.rs
+use crate::category::{categories, category_name, find_category, intake_summary, Category};
 use crate::commands::Command;
-use crate::state::{support_group_id, Language, StateContainer, TicketType};
-use crate::util::{get_random_topic_color, get_user_name};
-use chrono::Local;
+use crate::config::config;
+use crate::i18n::tr;
+use crate::relay::{
+    describe_message, relay_edit, relay_media_group, relay_message, relay_texts, MAX_TEXT_LENGTH,
+};
+use crate::routing::{is_customer_chat, is_support_group, route_ticket};
+use crate::state::{Language, PendingTicket, StateContainer};
+use crate::storage::{
+    CloseReason, NewNote, NewTicket, RelayedMessage, StaffMember, StorageError, Team, Ticket,
+    TicketStatus, UserProfile,
+};
+use crate::ticket::{
+    assign_ticket, close_ticket, format_topic_name, set_ticket_language, set_ticket_status,
+};
+use crate::util::{generate_invite_code, get_user_name, parse_duration};
+use chrono::{Local, Utc};
 use colored::Colorize;
 use std::sync::Arc;
-use teloxide::sugar::request::RequestReplyExt;
+use std::time::Duration;
 use teloxide::types::ParseMode;
+use teloxide::utils::html;
 use teloxide::{
     prelude::*,
-    types::{ChatKind, InlineKeyboardButton, InlineKeyboardMarkup, MessageKind},
+    types::{
+        ChatKind, InlineKeyboardButton, InlineKeyboardMarkup, Me, MessageEntityKind, MessageId,
+        ReactionType, ReplyParameters, ThreadId, User,
+    },
 };
 
-const CALLBACK_ITALIAN: &str = "lang_it";
-const CALLBACK_ENGLISH: &str = "lang_en";
+/// Prefix of the callback data of the language buttons of the ticket creation, followed by the language code
+const CALLBACK_LANGUAGE: &str = "lang:";
+/// Prefix of the callback data of the language buttons of `/language`, followed by the language code
+const CALLBACK_PREFERENCE: &str = "preference:";
 const CALLBACK_CANCEL: &str = "cancel";
 
-/// Creates an inline keyboard for language selection
-fn create_language_keyboard() -> InlineKeyboardMarkup {
+/// Creates an inline keyboard for language selection, with a button for each locale named in its own language.
+/// The `selected` language is marked, and the callback data of the buttons starts with `prefix`.
+fn create_language_keyboard(selected: Language, prefix: &str) -> InlineKeyboardMarkup {
+    let rows: Vec<Vec<InlineKeyboardButton>> = Language::all()
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .map(|row| {
+            row.iter()
+                .map(|&language| {
+                    let mark = if language == selected { "✅ " } else { "" };
+                    InlineKeyboardButton::callback(
+                        format!("{}{} {}", mark, language.to_flag(), language.name()),
+                        format!("{}{}", prefix, language.code()),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    InlineKeyboardMarkup::new(rows)
+}
+
+/// Remembers the customer, creating their profile on the first contact
+fn remember_user(state: &StateContainer, user: &User) {
+    if let Err(e) = state.storage.save_user(user.id, &get_user_name(user)) {
+        eprintln!("Error saving the profile of {}: {}", user.id, e);
+    }
+}
+
+/// Profile of the customer, if they already contacted the bot
+fn user_profile(state: &StateContainer, user: &User) -> Option<UserProfile> {
+    state.storage.user_profile(user.id).unwrap_or_else(|e| {
+        eprintln!("Error loading the profile of {}: {}", user.id, e);
+        None
+    })
+}
+
+/// Team with the id, logging database errors
+fn team_by_id(state: &StateContainer, id: i64) -> Option<Team> {
+    state.storage.team(id).unwrap_or_else(|e| {
+        eprintln!("Error loading team {}: {}", id, e);
+        None
+    })
+}
+
+/// Open tickets of the team the user joined, which they can see and answer
+fn team_tickets(state: &StateContainer, user: &User) -> Vec<Ticket> {
+    let Some(team_id) = user_profile(state, user).and_then(|p| p.team_id) else {
+        return Vec::new();
+    };
+    state
+        .storage
+        .active_tickets_by_team(team_id)
+        .unwrap_or_else(|e| {
+            eprintln!("Error loading the tickets of team {}: {}", team_id, e);
+            Vec::new()
+        })
+}
+
+/// Ticket a message of the customer is for: the team ticket of the message they reply to, their own ticket,
+/// or else the team ticket with the latest activity
+fn customer_ticket(state: &StateContainer, msg: &Message) -> Option<Ticket> {
+    let own = state.ticket_by_chat(msg.chat.id);
+    // The tickets of the customer groups are not shared with the teams
+    let Some(from) = msg.from.as_ref().filter(|_| msg.chat.is_private()) else {
+        return own;
+    };
+    let team = team_tickets(state, from);
+
+    let replied = msg.reply_to_message().and_then(|reply| {
+        let relayed = match state
+            .storage
+            .relayed_message_by_copy(reply.chat.id, reply.id)
+        {
+            Ok(None) => state
+                .storage
+                .relayed_copies(reply.chat.id, reply.id)
+                .map(|copies| copies.into_iter().next()),
+            result => result,
+        };
+        relayed.unwrap_or_else(|e| {
+            eprintln!("Error loading the copy of message {}: {}", reply.id, e);
+            None
+        })
+    });
+    if let Some(ticket) = replied.and_then(|r| team.iter().find(|t| t.id == r.ticket_id)) {
+        return Some(ticket.clone());
+    }
+
+    own.or_else(|| team.into_iter().max_by_key(|t| t.last_activity_at))
+}
+
+/// Private chats receiving the messages of the support group on the ticket: the customer who opened it first,
+/// then the other members of its team
+fn ticket_recipients(state: &StateContainer, ticket: &Ticket) -> Vec<ChatId> {
+    let mut recipients = vec![ticket.chat_id];
+    if let Some(team_id) = ticket.team_id {
+        let members = state.storage.team_members(team_id).unwrap_or_else(|e| {
+            eprintln!("Error loading the members of team {}: {}", team_id, e);
+            Vec::new()
+        });
+        recipients.extend(
+            members
+                .into_iter()
+                .map(ChatId::from)
+                .filter(|chat_id| *chat_id != ticket.chat_id),
+        );
+    }
+    recipients
+}
+
+/// Name signing the messages relayed to the topic, for the team members other than the customer who opened the ticket
+fn team_author(ticket: &Ticket, user: Option<&User>) -> Option<String> {
+    user.filter(|u| u.id != ticket.opened_by).map(get_user_name)
+}
+
+/// Language for the messages to the user: the one they chose, or the one of their Telegram profile
+fn user_language(state: &StateContainer, user: &User) -> Language {
+    user_profile(state, user)
+        .and_then(|profile| profile.language)
+        .or_else(|| user.language_code.as_deref().and_then(Language::detect))
+        .unwrap_or(Language::DEFAULT)
+}
+
+/// Remembers the language chosen by the user for the next tickets and messages
+fn save_user_language(state: &StateContainer, user: &User, language: Language) {
+    if let Err(e) = state.storage.set_user_language(user.id, language) {
+        eprintln!("Error saving the language of {}: {}", user.id, e);
+    }
+}
+
+/// Prefix of the callback data of the category buttons, followed by the category id
+const CALLBACK_CATEGORY: &str = "category:";
+
+/// Creates an inline keyboard for the category selection, with a button for each configured category
+fn create_category_keyboard(lang: Language) -> (String, InlineKeyboardMarkup) {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = categories()
+        .chunks(3)
+        .map(|row| {
+            row.iter()
+                .map(|category| {
+                    InlineKeyboardButton::callback(
+                        format!("{} {}", category.emoji, category.label(lang)),
+                        format!("{}{}", CALLBACK_CATEGORY, category.id),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    rows.push(vec![InlineKeyboardButton::callback(
+        tr!(lang, "button-cancel"),
+        CALLBACK_CANCEL,
+    )]);
+
+    (
+        tr!(lang, "choose-category"),
+        InlineKeyboardMarkup::new(rows),
+    )
+}
+
+const CALLBACK_INTAKE_SKIP: &str = "intake_skip";
+const CALLBACK_INTAKE_BACK: &str = "intake_back";
+
+/// Creates the inline keyboard of an intake question, going back only after the first one
+fn create_intake_keyboard(lang: Language, first: bool) -> InlineKeyboardMarkup {
+    let mut navigation = Vec::new();
+    if !first {
+        navigation.push(InlineKeyboardButton::callback(
+            tr!(lang, "button-back"),
+            CALLBACK_INTAKE_BACK,
+        ));
+    }
+    navigation.push(InlineKeyboardButton::callback(
+        tr!(lang, "button-skip"),
+        CALLBACK_INTAKE_SKIP,
+    ));
+
     InlineKeyboardMarkup::new(vec![
-        vec![
-            InlineKeyboardButton::callback("🇮🇹 Italiano", CALLBACK_ITALIAN),
-            InlineKeyboardButton::callback("🇬🇧 English", CALLBACK_ENGLISH),
-        ],
-        vec![InlineKeyboardButton::callback("Cancel", CALLBACK_CANCEL)],
+        navigation,
+        vec![InlineKeyboardButton::callback(
+            tr!(lang, "button-cancel"),
+            CALLBACK_CANCEL,
+        )],
     ])
 }
 
-const CALLBACK_BUG: &str = "ticket_bug";
-const CALLBACK_HOW_TO: &str = "ticket_how_to";
-const CALLBACK_OTHER: &str = "ticket_other";
+const CALLBACK_TEAM_SKIP: &str = "team_skip";
+
+/// Creates the inline keyboard of the team question
+fn create_team_keyboard(lang: Language) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback(
+            tr!(lang, "button-skip"),
+            CALLBACK_TEAM_SKIP,
+        )],
+        vec![InlineKeyboardButton::callback(
+            tr!(lang, "button-cancel"),
+            CALLBACK_CANCEL,
+        )],
+    ])
+}
+
+const CALLBACK_REOPEN: &str = "reopen";
+
+/// Creates the inline keyboard attached to the close confirmation
+fn create_reopen_keyboard(lang: Language) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        tr!(lang, "button-reopen"),
+        CALLBACK_REOPEN,
+    )]])
+}
+
+/// Reopens the last ticket of the private chat, if it was closed within the reopen window
+async fn reopen_ticket(
+    bot: &Bot,
+    state: &StateContainer,
+    chat_id: ChatId,
+    from: &User,
+) -> Result<(), teloxide::RequestError> {
+    if let Some(ticket) = state.ticket_by_chat(chat_id) {
+        bot.send_message(chat_id, tr!(ticket.language, "ticket-already-open"))
+            .await?;
+        return Ok(());
+    }
+    if !is_allowed(state, from) {
+        return refuse_access(bot, chat_id, from, user_language(state, from)).await;
+    }
+
+    let last_closed = state
+        .storage
+        .last_closed_ticket_by_chat(chat_id)
+        .unwrap_or_else(|e| {
+            eprintln!("Error loading the last ticket of chat {}: {}", chat_id, e);
+            None
+        });
+    let language = last_closed
+        .as_ref()
+        .map_or_else(|| user_language(state, from), |ticket| ticket.language);
+    let Some(mut ticket) = last_closed.filter(|t| {
+        t.closed_at
+            .is_some_and(|closed_at| Utc::now() - closed_at <= config().reopen_window)
+    }) else {
+        bot.send_message(chat_id, tr!(language, "no-ticket-to-reopen"))
+            .await?;
+        return Ok(());
+    };
+
+    set_ticket_status(bot, state, &mut ticket, TicketStatus::Open).await;
+
+    bot.send_message(
+        ticket.group_id,
+        tr!(
+            config().staff_language,
+            "staff-ticket-reopened",
+            user = get_user_name(from)
+        ),
+    )
+    .message_thread_id(ticket.thread_id)
+    .await?;
+
+    bot.send_message(chat_id, tr!(ticket.language, "ticket-reopened"))
+        .await?;
+
+    println!(
+        "{} {} {} reopened the ticket!",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[REOPEN]".bold().green(),
+        get_user_name(from).bold().blue()
+    );
+
+    Ok(())
+}
+
+/// Member of the support group corresponding to the Telegram user
+fn staff_member(user: &User) -> StaffMember {
+    StaffMember {
+        user_id: user.id,
+        username: user.username.clone(),
+        name: get_user_name(user),
+    }
+}
+
+/// Remembers the author of a message of the support group, so tickets can be assigned to them by username
+fn remember_staff_member(state: &StateContainer, msg: &Message) {
+    if let Some(user) = msg.from.as_ref().filter(|u| !u.is_bot) {
+        if let Err(e) = state.storage.save_staff_member(&staff_member(user)) {
+            eprintln!("Error saving the staff member {}: {}", user.id, e);
+        }
+    }
+}
+
+/// Finds the member targeted by `/assign`: a mentioned user without username, a known @username,
+/// or the author of the message the command replies to
+fn assign_target(state: &StateContainer, msg: &Message, arg: &str) -> Option<StaffMember> {
+    let text_mention = msg.entities().and_then(|entities| {
+        entities.iter().find_map(|entity| match &entity.kind {
+            MessageEntityKind::TextMention { user } => Some(staff_member(user)),
+            _ => None,
+        })
+    });
+    if text_mention.is_some() {
+        return text_mention;
+    }
+
+    let username = arg.trim().trim_start_matches('@');
+    if !username.is_empty() {
+        return state
+            .storage
+            .staff_member_by_username(username)
+            .unwrap_or_else(|e| {
+                eprintln!("Error loading the staff member @{}: {}", username, e);
+                None
+            });
+    }
+
+    replied_message(msg)
+        .and_then(|reply| reply.from.as_ref())
+        .filter(|user| !user.is_bot)
+        .map(staff_member)
+}
+
+/// Text of an internal note written with the configured prefix, if the message is one
+fn note_text(msg: &Message) -> Option<&str> {
+    let prefix = &config().note_prefix;
+    if prefix.is_empty() {
+        return None;
+    }
+    msg.text()
+        .or(msg.caption())
+        .and_then(|text| text.strip_prefix(prefix.as_str()))
+        .map(str::trim)
+}
+
+/// Stores an internal note on the ticket. The message stays in the topic, marked so it's clear it was not relayed.
+async fn save_note(
+    bot: &Bot,
+    state: &StateContainer,
+    msg: &Message,
+    ticket: &Ticket,
+    text: &str,
+) -> Result<(), teloxide::RequestError> {
+    let Some(author) = &msg.from else {
+        return Ok(());
+    };
+
+    if let Err(e) = state.storage.add_note(NewNote {
+        ticket_id: ticket.id,
+        author_id: author.id,
+        author_name: get_user_name(author),
+        text: text.to_string(),
+    }) {
+        eprintln!("Error saving the note on ticket #{}: {}", ticket.id, e);
+    }
+
+    bot.set_message_reaction(msg.chat.id, msg.id)
+        .reaction(vec![ReactionType::Emoji {
+            emoji: "✍".to_string(),
+        }])
+        .await?;
+
+    println!(
+        "{} {} {} added a note to the ticket of {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[NOTE]".bold().magenta(),
+        get_user_name(author).bold().blue(),
+        ticket.opener_name.bold().yellow()
+    );
+
+    Ok(())
+}
+
+/// Records the copy of a relayed message, so later edits can be applied to it
+fn remember_relayed_message(
+    state: &StateContainer,
+    ticket: &Ticket,
+    msg: &Message,
+    copy: &Message,
+) {
+    if let Err(e) = state.storage.save_relayed_message(&RelayedMessage {
+        ticket_id: ticket.id,
+        source_chat_id: msg.chat.id,
+        source_message_id: msg.id,
+        source_user_id: msg.from.as_ref().map(|from| from.id),
+        copy_chat_id: copy.chat.id,
+        copy_message_id: copy.id,
+        coalesced: false,
+    }) {
+        eprintln!("Error saving the copy of message {}: {}", msg.id, e);
+    }
+}
+
+/// Message `msg` really replies to, if any. In topics every message replies to the topic creation, which is neither
+/// relayed nor sent by a member of the support group.
+fn replied_message(msg: &Message) -> Option<&Message> {
+    msg.reply_to_message()
+        .filter(|reply| reply.forum_topic_created().is_none())
+}
+
+/// Message of `chat_id`, on the other side of the relay, matching the one `msg` replies to, if any
+fn relayed_reply_target(
+    state: &StateContainer,
+    msg: &Message,
+    chat_id: ChatId,
+) -> Option<MessageId> {
+    let reply = replied_message(msg)?;
+
+    // The replied message is either relayed from this chat, or the copy of a message of the other side
+    let target = match state.storage.relayed_copies(reply.chat.id, reply.id) {
+        Ok(copies) if !copies.is_empty() => Ok(copies
+            .into_iter()
+            .find(|r| r.copy_chat_id == chat_id)
+            .map(|r| r.copy_message_id)),
+        Ok(_) => state
+            .storage
+            .relayed_message_by_copy(reply.chat.id, reply.id)
+            .map(|relayed| {
+                relayed
+                    .filter(|r| r.source_chat_id == chat_id)
+                    .map(|r| r.source_message_id)
+            }),
+        Err(e) => Err(e),
+    };
+
+    target.unwrap_or_else(|e| {
+        eprintln!("Error loading the copy of message {}: {}", reply.id, e);
+        None
+    })
+}
+
+/// Relays a message of the support group to the members of the team of the ticket, besides the customer who
+/// opened it. Errors are only logged, since the members may have never started the bot.
+async fn relay_to_team_members(bot: &Bot, state: &StateContainer, ticket: &Ticket, msg: &Message) {
+    for chat_id in ticket_recipients(state, ticket).into_iter().skip(1) {
+        let reply_to = relayed_reply_target(state, msg, chat_id);
+        match relay_message(bot, msg, chat_id, None, reply_to, None).await {
+            Ok(Some(copy)) => remember_relayed_message(state, ticket, msg, &copy),
+            Ok(None) => {}
+            Err(e) => eprintln!("Error relaying message {} to {}: {}", msg.id, chat_id, e),
+        }
+    }
+}
+
+/// Collects the items of an album, which Telegram sends as separate messages sharing the `media_group_id`.
+/// The first item schedules the relay of the whole album to the `targets` after a short wait, signed by the
+/// `author` if any. The albums going to a topic wait for the rate limits too.
+async fn buffer_album(
+    bot: &Bot,
+    state: &Arc<StateContainer>,
+    msg: &Message,
+    ticket: Ticket,
+    targets: Vec<ChatId>,
+    thread_id: Option<ThreadId>,
+    author: Option<String>,
+) {
+    let Some(group_id) = msg.media_group_id().map(str::to_string) else {
+        return;
+    };
+
+    let mut albums = state.albums.lock().await;
+    let album = albums.entry(group_id.clone()).or_default();
+    album.push(msg.clone());
+    if album.len() > 1 {
+        return;
+    }
+    drop(albums);
+
+    let bot = bot.clone();
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(config().media_group_wait.to_std().unwrap_or_default()).await;
+
+        let Some(mut messages) = state.albums.lock().await.remove(&group_id) else {
+            return;
+        };
+        messages.sort_by_key(|m| m.id.0);
+
+        // An album going to a topic takes one token of the customer and of the support group, like a message
+        if let Some(from) = messages[0].from.as_ref().filter(|_| thread_id.is_some()) {
+            while let Err(wait) = state.rate_limiter.acquire(from.id, ticket.group_id) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        let author = author.as_deref();
+        if let Err(e) = relay_album(
+            &bot, &state, &ticket, &messages, &targets, thread_id, author,
+        )
+        .await
+        {
+            eprintln!("Error relaying the album of ticket #{}: {}", ticket.id, e);
+        }
+    });
+}
+
+/// Result of sending to the recipient at `index` of a ticket, the customer who opened it coming first. Only failing
+/// with them is an error: the failures with the rest of the team, who may have never started the bot, are logged.
+fn fan_out_result<T>(
+    index: usize,
+    chat_id: ChatId,
+    action: &str,
+    result: Result<T, teloxide::RequestError>,
+) -> Result<Option<T>, teloxide::RequestError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if index > 0 => {
+            eprintln!("Error {} to {}: {}", action, chat_id, e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Relays the items of an album together to each of the `targets`, the customer who opened the ticket first
+async fn relay_album(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &Ticket,
+    messages: &[Message],
+    targets: &[ChatId],
+    thread_id: Option<ThreadId>,
+    author: Option<&str>,
+) -> Result<(), teloxide::RequestError> {
+    let mut relayed = 0;
+    for (index, &chat_id) in targets.iter().enumerate() {
+        let reply_to = relayed_reply_target(state, &messages[0], chat_id);
+        let result = relay_media_group(bot, messages, chat_id, thread_id, reply_to, author).await;
+        let Some(copies) = fan_out_result(index, chat_id, "relaying the album", result)? else {
+            continue;
+        };
+        for (msg, copy) in &copies {
+            remember_relayed_message(state, ticket, msg, copy);
+        }
+        relayed = relayed.max(copies.len());
+    }
+    if relayed == 0 {
+        return Ok(());
+    }
+
+    if let Err(e) = state.storage.touch_ticket(ticket.id) {
+        eprintln!("Error updating ticket #{}: {}", ticket.id, e);
+    }
+
+    println!(
+        "{} {} {} sent an album of {} items",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[ALBUM]".bold().blue(),
+        get_user_name(&messages[0].from.clone().unwrap())
+            .bold()
+            .yellow(),
+        relayed
+    );
+
+    Ok(())
+}
+
+/// Summary of the profile of the customer for the support group, posted when a ticket is opened.
+/// The team they joined is shown instead of the one they declared, if any.
+fn profile_card(profile: &UserProfile, team: Option<&Team>, ticket_language: Language) -> String {
+    let staff_language = config().staff_language;
+    let mut lines = vec![tr!(
+        staff_language,
+        "staff-profile-name",
+        name = profile.display_name.clone()
+    )];
+    if let Some(team) = team.map(|t| &t.name).or(profile.team.as_ref()) {
+        lines.push(tr!(
+            staff_language,
+            "staff-profile-team",
+            team = team.clone()
+        ));
+    }
+    lines.push(tr!(
+        staff_language,
+        "staff-profile-language",
+        language = format!("{} {}", ticket_language.to_flag(), ticket_language.name())
+    ));
+    lines.push(tr!(
+        staff_language,
+        "staff-profile-history",
+        since = profile.first_contact_at.format("%Y-%m-%d").to_string(),
+        tickets = profile.ticket_count
+    ));
+    lines.join("\n")
+}
+
+/// Creates the topic and the ticket once the user chose the category and filled its intake form.
+/// The answers to the intake questions are pinned at the top of the topic.
+async fn open_ticket(
+    bot: &Bot,
+    state: &StateContainer,
+    chat_id: ChatId,
+    from: &User,
+    language: Language,
+    category: &Category,
+    answers: &[Option<String>],
+) -> Result<(), teloxide::RequestError> {
+    // The members of the team of the customer share the ticket. Tickets opened in a group show its title instead.
+    let profile = user_profile(state, from);
+    let team = profile
+        .as_ref()
+        .and_then(|p| p.team_id)
+        .filter(|_| chat_id.is_user())
+        .and_then(|id| team_by_id(state, id));
+    let team_name = match &team {
+        Some(team) => Some(team.name.clone()),
+        None if !chat_id.is_user() => bot.get_chat(chat_id).await?.title().map(str::to_string),
+        None => None,
+    };
+
+    // Create topic with the category in the name, in the support group the ticket is routed to
+    let group_id = route_ticket(category, language);
+    let topic_name = format_topic_name(
+        TicketStatus::Open,
+        language,
+        &category.id,
+        &get_user_name(from),
+        team_name.as_deref(),
+        None,
+    );
+
+    let topic = bot
+        .create_forum_topic(
+            group_id,
+            &topic_name,
+            category.topic_color(),
+            "New support ticket",
+        )
+        .await?;
+
+    // Save the ticket bound to the created topic
+    if let Err(e) = state.storage.create_ticket(NewTicket {
+        chat_id,
+        group_id,
+        thread_id: topic.thread_id,
+        language,
+        category: category.id.clone(),
+        opened_by: from.id,
+        opener_name: get_user_name(from),
+        team_id: team.as_ref().map(|t| t.id),
+        team_name,
+    }) {
+        // A topic without its ticket would swallow the messages: remove it and let the customer try again
+        eprintln!("Error saving the ticket: {}", e);
+        if let Err(e) = bot.delete_forum_topic(group_id, topic.thread_id).await {
+            eprintln!("Error deleting the topic of the unsaved ticket: {}", e);
+        }
+        bot.send_message(chat_id, tr!(language, "ticket-creation-failed"))
+            .await?;
+        return Ok(());
+    }
+
+    // The ticket is saved, so failing to fill the topic must not keep the confirmation from the customer.
+    // Tell the support group who the customer is. The ticket counts in the profile loaded before saving it.
+    if let Some(mut profile) = profile {
+        profile.ticket_count += 1;
+        if let Err(e) = bot
+            .send_message(group_id, profile_card(&profile, team.as_ref(), language))
+            .message_thread_id(topic.thread_id)
+            .await
+        {
+            eprintln!("Error sending the profile of {}: {}", from.id, e);
+        }
+    }
+
+    if !answers.is_empty() {
+        let summary = bot
+            .send_message(group_id, intake_summary(category, answers))
+            .message_thread_id(topic.thread_id)
+            .parse_mode(ParseMode::Html)
+            .await;
+        let pinned = match summary {
+            Ok(summary) => {
+                bot.pin_chat_message(group_id, summary.id)
+                    .disable_notification(true)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = pinned {
+            eprintln!("Error posting the intake answers of {}: {}", from.id, e);
+        }
+    }
+
+    // Send confirmation message with the category, telling the groups how to write to RustBusters
+    let category_label = html::escape(category.label(language));
+    let confirmation = if chat_id.is_user() {
+        tr!(language, "ticket-created", category = category_label)
+    } else {
+        tr!(language, "ticket-created-group", category = category_label)
+    };
+    bot.send_message(chat_id, confirmation)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    println!(
+        "{} {} {} created a new ticket of category {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        "[NEW]".bold().yellow(),
+        get_user_name(from).bold().blue(),
+        category.name.bold().cyan()
+    );
+
+    Ok(())
+}
+
+/// Moves the ticket creation on once the language is known: the team of the users that never opened a ticket
+/// is asked first, then the category. The steps already known from the user profile are skipped.
+async fn ask_team_or_category(
+    bot: &Bot,
+    state: &StateContainer,
+    chat_id: ChatId,
+    from: &User,
+    language: Language,
+    mut pending: PendingTicket,
+) -> Result<(), teloxide::RequestError> {
+    // The team is asked in the private chats only, the tickets of the groups don't belong to a team
+    let ask_team = !pending.team_asked
+        && chat_id.is_user()
+        && user_profile(state, from).is_none_or(|profile| {
+            profile.team.is_none() && profile.team_id.is_none() && profile.ticket_count == 0
+        });
+
+    let sent = if ask_team {
+        pending.team_asked = true;
+        bot.send_message(chat_id, tr!(language, "ask-team"))
+            .reply_markup(create_team_keyboard(language))
+            .await?
+    } else {
+        let (prompt, keyboard) = create_category_keyboard(language);
+        bot.send_message(chat_id, prompt)
+            .reply_markup(keyboard)
+            .await?
+    };
+    pending.team_question = ask_team.then_some(sent.id);
+    state.pending.lock().await.insert(chat_id, pending);
+
+    Ok(())
+}
+
+/// Moves the ticket creation to the intake question after the answered ones, or opens the ticket when all are answered
+async fn next_intake_step(
+    bot: &Bot,
+    state: &StateContainer,
+    chat_id: ChatId,
+    from: &User,
+    language: Language,
+    category: &Category,
+    mut pending: PendingTicket,
+) -> Result<(), teloxide::RequestError> {
+    // Remove the buttons of the answered question
+    if let Some(question_message) = pending.question_message.take() {
+        bot.edit_message_reply_markup(chat_id, question_message)
+            .await?;
+    }
+
+    let questions = &category.questions;
+    let Some(question) = questions.get(pending.answers.len()) else {
+        return open_ticket(
+            bot,
+            state,
+            chat_id,
+            from,
+            language,
+            category,
+            &pending.answers,
+        )
+        .await;
+    };
+
+    let mut text = format!(
+        "({}/{}) {}",
+        pending.answers.len() + 1,
+        questions.len(),
+        question.text(language)
+    );
+    // In the groups the bot only gets the answers replying to its messages
+    if !chat_id.is_user() {
+        text = format!("{}\n\n{}", text, tr!(language, "intake-reply-hint"));
+    }
+    let sent = bot
+        .send_message(chat_id, text)
+        .reply_markup(create_intake_keyboard(language, pending.answers.is_empty()))
+        .await?;
+    pending.question_message = Some(sent.id);
+    state.pending.lock().await.insert(chat_id, pending);
+
+    Ok(())
+}
+
+/// Records a message of a user without an open ticket as the answer to the current intake question, if any
+async fn answer_intake_question(
+    bot: &Bot,
+    state: &StateContainer,
+    msg: &Message,
+) -> Result<(), teloxide::RequestError> {
+    let chat_id = msg.chat.id;
+    let Some(from) = &msg.from else {
+        return Ok(());
+    };
+
+    let mut pending_map = state.pending.lock().await;
+
+    // The answer to the team question, asked before the category
+    if let Some(pending) = pending_map
+        .get(&chat_id)
+        .filter(|p| p.team_question.is_some())
+    {
+        let Some(language) = pending.language else {
+            return Ok(());
+        };
+        let Some(team) = msg.text().map(str::trim).filter(|t| !t.is_empty()) else {
+            drop(pending_map);
+            bot.send_message(chat_id, tr!(language, "intake-text-only"))
+                .await?;
+            return Ok(());
+        };
+
+        let mut pending = pending_map.remove(&chat_id).unwrap_or_default();
+        drop(pending_map);
+        if let Err(e) = state.storage.set_user_team(from.id, team) {
+            eprintln!("Error saving the team of {}: {}", from.id, e);
+        }
+        if let Some(question) = pending.team_question.take() {
+            bot.edit_message_reply_markup(chat_id, question).await?;
+        }
+        return ask_team_or_category(bot, state, chat_id, from, language, pending).await;
+    }
+
+    let Some((language, category)) = pending_map
+        .get(&chat_id)
+        .and_then(|p| Some((p.language?, find_category(p.category.as_deref()?)?)))
+    else {
+        return Ok(());
+    };
+
+    let Some(text) = msg.text() else {
+        drop(pending_map);
+        bot.send_message(chat_id, tr!(language, "intake-text-only"))
+            .await?;
+        return Ok(());
+    };
+
+    let mut pending = pending_map.remove(&chat_id).unwrap_or_default();
+    drop(pending_map);
+    pending.answers.push(Some(text.to_string()));
+
+    next_intake_step(bot, state, chat_id, from, language, category, pending).await
+}
+
+/// Handles bot commands
+pub async fn handle_commands(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<StateContainer>,
+) -> Result<(), teloxide::RequestError> {
+    if is_support_group(msg.chat.id) {
+        remember_staff_member(&state, &msg);
+    }
+    if let Some(from) = msg.from.as_ref().filter(|_| is_customer_chat(&msg.chat)) {
+        // Banned and muted customers are ignored
+        if is_blocked(&state, from) {
+            return Ok(());
+        }
+        remember_user(&state, from);
+    }
+
+    match cmd {
+        Command::Start => {
+            bot.send_message(
+                msg.chat.id,
+                tr!(user_language(&state, &msg.from.clone().unwrap()), "welcome"),
+            )
+            .await?;
+        }
+        Command::GetId => {
+            bot.send_message(msg.chat.id, msg.chat.id.0.to_string())
+                .await?;
+        }
+        Command::Support => {
+            let from = msg.from.clone().unwrap();
+            let language = user_language(&state, &from);
+
+            // Verify command was sent in a private chat or in a group of the customers
+            if !is_customer_chat(&msg.chat) {
+                bot.send_message(msg.chat.id, tr!(language, "customer-chat-only"))
+                    .await?;
+                return Ok(());
+            }
+
+            // Check if the chat already has an open ticket
+            if let Some(ticket) = state.ticket_by_chat(msg.chat.id) {
+                bot.send_message(msg.chat.id, tr!(ticket.language, "ticket-already-open"))
+                    .await?;
+                return Ok(());
+            }
+            if !is_allowed(&state, &from) {
+                return refuse_access(&bot, msg.chat.id, &from, language).await;
+            }
+
+            println!(
+                "{} {} {} started a new ticket",
+                Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                "[NEW]".bold().green(),
+                get_user_name(&from).bold().blue()
+            );
+
+            // Start a new creation wizard, discarding any previous one of the same user.
+            // Users who already chose their language don't need to choose it again.
+            if let Some(language) = user_profile(&state, &from).and_then(|p| p.language) {
+                let pending = PendingTicket {
+                    language: Some(language),
+                    ..PendingTicket::default()
+                };
+                return ask_team_or_category(&bot, &state, msg.chat.id, &from, language, pending)
+                    .await;
+            }
+            state
+                .pending
+                .lock()
+                .await
+                .insert(msg.chat.id, PendingTicket::default());
+
+            // Send language selection message, with the language of the user already marked
+            let keyboard = create_language_keyboard(language, CALLBACK_LANGUAGE).append_row(vec![
+                InlineKeyboardButton::callback(tr!(language, "button-cancel"), CALLBACK_CANCEL),
+            ]);
+            bot.send_message(msg.chat.id, tr!(language, "choose-language"))
+                .reply_markup(keyboard)
+                .await?;
+        }
+        Command::Close => {
+            if let Some(mut ticket) = state.ticket_by_chat(msg.chat.id) {
+                // Notify the topic that the user ended the chat
+                bot.send_message(
+                    ticket.group_id,
+                    tr!(
+                        config().staff_language,
+                        "staff-chat-ended-by-user",
+                        user = get_user_name(&msg.from.clone().unwrap())
+                    ),
+                )
+                .message_thread_id(ticket.thread_id)
+                .await?;
+
+                bot.send_message(msg.chat.id, tr!(ticket.language, "ticket-closed-by-user"))
+                    .reply_markup(create_reopen_keyboard(ticket.language))
+                    .await?;
+
+                close_ticket(&bot, &state, &mut ticket, CloseReason::User).await;
+
+                println!(
+                    "{} {} {} closed the ticket!",
+                    Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                    "[CLOSE]".bold().red(),
+                    get_user_name(&msg.from.clone().unwrap()).bold().yellow()
+                )
+            } else if is_support_group(msg.chat.id) && msg.is_topic_message {
+                if let Some(mut ticket) = msg
+                    .thread_id
+                    .and_then(|t| state.ticket_by_thread(msg.chat.id, t))
+                {
+                    bot.send_message(
+                        ticket.group_id,
+                        tr!(config().staff_language, "staff-chat-ended"),
+                    )
+                    .message_thread_id(ticket.thread_id)
+                    .await?;
+                    bot.send_message(
+                        ticket.chat_id,
+                        tr!(ticket.language, "ticket-closed-by-staff"),
+                    )
+                    .reply_markup(create_reopen_keyboard(ticket.language))
+                    .await?;
+                    println!(
+                        "{} {} {} closed the ticket!",
+                        Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                        "[CLOSE - RB]".bold().red(),
+                        get_user_name(&msg.from.clone().unwrap()).bold().blue()
+                    );
+
+                    close_ticket(&bot, &state, &mut ticket, CloseReason::Staff).await;
+                }
+            }
+        }
+        Command::Reopen => {
+            if is_customer_chat(&msg.chat) {
+                reopen_ticket(&bot, &state, msg.chat.id, &msg.from.clone().unwrap()).await?;
+            }
+        }
+        Command::Language => {
+            let Some(from) = &msg.from else {
+                return Ok(());
+            };
+            let language = user_language(&state, from);
+            if !matches!(msg.chat.kind, ChatKind::Private(_)) {
+                bot.send_message(msg.chat.id, tr!(language, "private-chat-only"))
+                    .await?;
+                return Ok(());
+            }
+
+            bot.send_message(msg.chat.id, tr!(language, "choose-language-preference"))
+                .reply_markup(create_language_keyboard(language, CALLBACK_PREFERENCE))
+                .await?;
+        }
+        Command::Resolve => {
+            if !is_support_group(msg.chat.id) || !msg.is_topic_message {
+                return Ok(());
+            }
+
+            if let Some(mut ticket) = msg
+                .thread_id
+                .and_then(|t| state.ticket_by_thread(msg.chat.id, t))
+            {
+                if ticket.status == TicketStatus::Resolved {
+                    return Ok(());
+                }
+
+                set_ticket_status(&bot, &state, &mut ticket, TicketStatus::Resolved).await;
+
+                // The whole team shares the ticket
+                for (index, chat_id) in ticket_recipients(&state, &ticket).into_iter().enumerate() {
+                    let result = bot
+                        .send_message(chat_id, tr!(ticket.language, "ticket-resolved"))
+                        .await;
+                    fan_out_result(index, chat_id, "sending the resolved notice", result)?;
+                }
+
+                println!(
+                    "{} {} {} resolved the ticket of {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    "[RESOLVE]".bold().green(),
+                    get_user_name(&msg.from.clone().unwrap()).bold().blue(),
+                    ticket.opener_name.bold().yellow()
+                );
+            }
+        }
+        Command::Claim | Command::Assign(_) => {
+            if !is_support_group(msg.chat.id) || !msg.is_topic_message {
+                return Ok(());
+            }
+            let Some(mut ticket) = msg
+                .thread_id
+                .and_then(|t| state.ticket_by_thread(msg.chat.id, t))
+            else {
+                return Ok(());
+            };
+
+            let assignee = match &cmd {
+                Command::Assign(arg) => assign_target(&state, &msg, arg),
+                _ => msg.from.as_ref().map(staff_member),
+            };
+            let Some(assignee) = assignee else {
+                bot.send_message(
+                    ticket.group_id,
+                    tr!(config().staff_language, "staff-unknown-member"),
+                )
+                .message_thread_id(ticket.thread_id)
+                .await?;
+                return Ok(());
+            };
+
+            assign_ticket(&bot, &state, &mut ticket, &assignee).await;
+
+            bot.send_message(
+                ticket.group_id,
+                tr!(
+                    config().staff_language,
+                    "staff-ticket-assigned",
+                    member = assignee.name.clone()
+                ),
+            )
+            .message_thread_id(ticket.thread_id)
+            .await?;
+
+            println!(
+                "{} {} {} assigned the ticket of {} to {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                "[ASSIGN]".bold().cyan(),
+                get_user_name(&msg.from.clone().unwrap()).bold().blue(),
+                ticket.opener_name.bold().yellow(),
+                assignee.name.bold().blue()
+            );
+        }
+        Command::Mine => {
+            if !is_support_group(msg.chat.id) {
+                return Ok(());
+            }
+            let Some(from) = &msg.from else {
+                return Ok(());
+            };
+
+            let tickets = state
+                .storage
+                .tickets_assigned_to(from.id)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error loading the tickets of {}: {}", from.id, e);
+                    Vec::new()
+                });
+
+            let text = if tickets.is_empty() {
+                tr!(config().staff_language, "staff-no-assigned-tickets")
+            } else {
+                format!(
+                    "{}\n\n{}",
+                    tr!(config().staff_language, "staff-assigned-tickets"),
+                    ticket_list(&tickets, &msg)
+                )
+            };
+            reply_in_group(&bot, &msg, text).await?;
+        }
+        Command::Join(code) => {
+            let Some(from) = &msg.from else {
+                return Ok(());
+            };
+            let language = user_language(&state, from);
+            if !matches!(msg.chat.kind, ChatKind::Private(_)) {
+                bot.send_message(msg.chat.id, tr!(language, "private-chat-only"))
+                    .await?;
+                return Ok(());
+            }
+
+            // The codes are shown in upper case, but typing them in lower case is fine too
+            let code = code.trim().to_uppercase();
+            if code.is_empty() {
+                bot.send_message(msg.chat.id, tr!(language, "team-code-empty"))
+                    .await?;
+                return Ok(());
+            }
+            let team = state
+                .storage
+                .team_by_invite_code(&code)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error loading the team with code {}: {}", code, e);
+                    None
+                });
+            let Some(team) = team else {
+                bot.send_message(msg.chat.id, tr!(language, "team-invalid-code"))
+                    .await?;
+                return Ok(());
+            };
+
+            if let Err(e) = state.storage.join_team(from.id, team.id) {
+                eprintln!("Error adding {} to team {}: {}", from.id, team.name, e);
+                return Ok(());
+            }
+            bot.send_message(
+                msg.chat.id,
+                tr!(language, "team-joined", team = team.name.clone()),
+            )
+            .await?;
+
+            println!(
+                "{} {} {} joined the team {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                "[TEAM]".bold().magenta(),
+                get_user_name(from).bold().yellow(),
+                team.name.bold().cyan()
+            );
+        }
+        Command::NewTeam(name) => {
+            if !is_support_group(msg.chat.id) {
+                return Ok(());
+            }
+            let staff_language = config().staff_language;
+            let name = name.trim();
+            if name.is_empty() {
+                return reply_in_group(&bot, &msg, tr!(staff_language, "staff-team-name-empty"))
+                    .await;
+            }
+
+            let text = match create_team(&state, name) {
+                Ok(team) => {
+                    println!(
+                        "{} {} {} created the team {}",
+                        Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        "[TEAM]".bold().magenta(),
+                        get_user_name(&msg.from.clone().unwrap()).bold().blue(),
+                        team.name.bold().cyan()
+                    );
+                    tr!(
+                        staff_language,
+                        "staff-team-created",
+                        team = team.name,
+                        code = team.invite_code
+                    )
+                }
+                Err(StorageError::Conflict(_)) => match find_team(&state, name) {
+                    Some(team) => tr!(
+                        staff_language,
+                        "staff-team-exists",
+                        team = team.name,
+                        code = team.invite_code
+                    ),
+                    None => return Ok(()),
+                },
+                Err(e) => {
+                    eprintln!("Error creating the team {}: {}", name, e);
+                    return Ok(());
+                }
+            };
+            reply_in_group(&bot, &msg, text).await?;
+        }
+        Command::Team(name) => {
+            if !is_support_group(msg.chat.id) {
+                return Ok(());
+            }
+            let staff_language = config().staff_language;
+            let name = name.trim();
+            if name.is_empty() {
+                return reply_in_group(&bot, &msg, tr!(staff_language, "staff-team-name-empty"))
+                    .await;
+            }
+            let Some(team) = find_team(&state, name) else {
+                return reply_in_group(
+                    &bot,
+                    &msg,
+                    tr!(staff_language, "staff-team-unknown", team = name),
+                )
+                .await;
+            };
+
+            let members = state.storage.team_members(team.id).unwrap_or_else(|e| {
+                eprintln!("Error loading the members of team {}: {}", team.name, e);
+                Vec::new()
+            });
+            let tickets = state
+                .storage
+                .active_tickets_by_team(team.id)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error loading the tickets of team {}: {}", team.name, e);
+                    Vec::new()
+                });
+
+            let header = tr!(
+                staff_language,
+                "staff-team-summary",
+                team = team.name.clone(),
+                code = team.invite_code.clone(),
+                members = members.len()
+            );
+            let text = if tickets.is_empty() {
+                format!(
+                    "{}\n\n{}",
+                    header,
+                    tr!(staff_language, "staff-team-no-tickets")
+                )
+            } else {
+                format!("{}\n\n{}", header, ticket_list(&tickets, &msg))
+            };
+            reply_in_group(&bot, &msg, text).await?;
+        }
+        Command::Redeem(code) => {
+            let Some(from) = &msg.from else {
+                return Ok(());
+            };
+            let language = user_language(&state, from);
+            if !matches!(msg.chat.kind, ChatKind::Private(_)) {
+                bot.send_message(msg.chat.id, tr!(language, "private-chat-only"))
+                    .await?;
+                return Ok(());
+            }
+
+            let code = code.trim().to_uppercase();
+            if code.is_empty() {
+                bot.send_message(msg.chat.id, tr!(language, "license-code-empty"))
+                    .await?;
+                return Ok(());
+            }
+            let license = state
+                .storage
+                .redeem_license(&code, from.id)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error redeeming the license {}: {}", code, e);
+                    None
+                });
+            if license.is_none() {
+                bot.send_message(msg.chat.id, tr!(language, "license-invalid-code"))
+                    .await?;
+                return Ok(());
+            }
+            bot.send_message(msg.chat.id, tr!(language, "license-redeemed"))
+                .await?;
+
+            println!(
+                "{} {} {} redeemed the license {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                "[ALLOWLIST]".bold().magenta(),
+                get_user_name(from).bold().yellow(),
+                code.bold().cyan()
+            );
+        }
+        Command::Allow(arg) => update_allowlist(&bot, &state, &msg, &arg, true).await?,
+        Command::Revoke(arg) => update_allowlist(&bot, &state, &msg, &arg, false).await?,
+        Command::Allowlist => {
+            if !is_support_admin(&bot, &msg).await? {
+                return Ok(());
+            }
+            let staff_language = config().staff_language;
+            let users = state.storage.allowed_users().unwrap_or_else(|e| {
+                eprintln!("Error loading the allowed users: {}", e);
+                Vec::new()
+            });
+            let teams = state.storage.allowed_teams().unwrap_or_else(|e| {
+                eprintln!("Error loading the allowed teams: {}", e);
+                Vec::new()
+            });
+
+            let mut lines = vec![tr!(
+                staff_language,
+                "staff-allowlist-summary",
+                users = users.len(),
+                teams = teams.len()
+            )];
+            lines.extend(
+                users
+                    .iter()
+                    .map(|user| format!("👤 {} - {}", user.user_id, user.display_name)),
+            );
+            lines.extend(teams.iter().map(|team| format!("👥 {}", team.name)));
+            if !config().allowlist_enabled {
+                lines.push(tr!(staff_language, "staff-allowlist-disabled"));
+            }
+            reply_in_group(&bot, &msg, lines.join("\n")).await?;
+        }
+        Command::License => {
+            if !is_support_admin(&bot, &msg).await? {
+                return Ok(());
+            }
+            let license = match state.storage.create_license(&generate_invite_code()) {
+                Ok(license) => license,
+                Err(e) => {
+                    eprintln!("Error creating a license: {}", e);
+                    return Ok(());
+                }
+            };
+            reply_in_group(
+                &bot,
+                &msg,
+                tr!(
+                    config().staff_language,
+                    "staff-license-created",
+                    code = license.code.clone()
+                ),
+            )
+            .await?;
+
+            println!(
+                "{} {} {} created the license {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                "[ALLOWLIST]".bold().magenta(),
+                get_user_name(&msg.from.clone().unwrap()).bold().blue(),
+                license.code.bold().cyan()
+            );
+        }
+        Command::Ban => block_customer(&bot, &state, &msg, None).await?,
+        Command::Mute(duration) => block_customer(&bot, &state, &msg, Some(&duration)).await?,
+        Command::Unban(arg) => unblock_customer(&bot, &state, &msg, &arg).await?,
+        Command::Note(text) => {
+            if !is_support_group(msg.chat.id) || !msg.is_topic_message {
+                return Ok(());
+            }
+            let Some(ticket) = msg
+                .thread_id
+                .and_then(|t| state.ticket_by_thread(msg.chat.id, t))
+            else {
+                return Ok(());
+            };
+
+            if text.trim().is_empty() {
+                bot.send_message(
+                    ticket.group_id,
+                    tr!(config().staff_language, "staff-note-empty"),
+                )
+                .message_thread_id(ticket.thread_id)
+                .await?;
+                return Ok(());
+            }
+
+            save_note(&bot, &state, &msg, &ticket, text.trim()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// One line for each ticket with a link to its topic, for the lists sent in the support groups
+fn ticket_list(tickets: &[Ticket], msg: &Message) -> String {
+    let lines: Vec<String> = tickets
+        .iter()
+        .map(|ticket| {
+            // The username is only known for the group the command was sent in
+            let username = (ticket.group_id == msg.chat.id)
+                .then(|| msg.chat.username())
+                .flatten();
+            let link = Message::url_of(ticket.group_id, username, ticket.thread_id.0)
+                .map(|url| url.to_string())
+                .unwrap_or_default();
+            format!(
+                "{} #{} {} - {} {}",
+                ticket.status.topic_prefix(),
+                ticket.id,
+                category_name(&ticket.category),
+                ticket.opener_name,
+                link
+            )
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Answers a command of the support group, in the topic it was sent in if any
+async fn reply_in_group(
+    bot: &Bot,
+    msg: &Message,
+    text: String,
+) -> Result<(), teloxide::RequestError> {
+    let mut reply = bot.send_message(msg.chat.id, text);
+    if let Some(thread_id) = msg.thread_id.filter(|_| msg.is_topic_message) {
+        reply = reply.message_thread_id(thread_id);
+    }
+    reply.await?;
+    Ok(())
+}
+
+/// Team with the name, ignoring the case, logging database errors
+fn find_team(state: &StateContainer, name: &str) -> Option<Team> {
+    state.storage.team_by_name(name).unwrap_or_else(|e| {
+        eprintln!("Error loading the team {}: {}", name, e);
+        None
+    })
+}
+
+/// Invite codes drawn before giving up on creating a team
+const INVITE_CODE_ATTEMPTS: u32 = 5;
+
+/// Creates the team with a random invite code, drawing a new one when the code is already taken.
+/// Fails with a conflict when the name is taken.
+fn create_team(state: &StateContainer, name: &str) -> Result<Team, StorageError> {
+    let mut attempts = 1;
+    loop {
+        match state.storage.create_team(name, &generate_invite_code()) {
+            Err(StorageError::Conflict(_))
+                if attempts < INVITE_CODE_ATTEMPTS && find_team(state, name).is_none() =>
+            {
+                attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether the customer can open tickets: everyone when the allowlist is disabled, otherwise the customers in the
+/// allowlist and the members of the allowed teams
+fn is_allowed(state: &StateContainer, user: &User) -> bool {
+    if !config().allowlist_enabled {
+        return true;
+    }
+    let Some(profile) = user_profile(state, user) else {
+        return false;
+    };
+    profile.allowed
+        || profile
+            .team_id
+            .and_then(|id| team_by_id(state, id))
+            .is_some_and(|team| team.allowed)
+}
+
+/// Politely refuses a ticket to a customer outside the allowlist, telling them how to get in touch
+async fn refuse_access(
+    bot: &Bot,
+    chat_id: ChatId,
+    from: &User,
+    language: Language,
+) -> Result<(), teloxide::RequestError> {
+    let text = match &config().sales_contact {
+        Some(contact) => tr!(language, "access-denied-contact", contact = contact.clone()),
+        None => tr!(language, "access-denied"),
+    };
+    bot.send_message(chat_id, text).await?;
+
+    println!(
+        "{} {} {} ({}) is not in the allowlist",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[DENIED]".bold().red(),
+        get_user_name(from).bold().yellow(),
+        from.id
+    );
+    Ok(())
+}
+
+/// Whether the command was sent by an administrator of the support group, telling the others that it is not for them
+async fn is_support_admin(bot: &Bot, msg: &Message) -> Result<bool, teloxide::RequestError> {
+    let Some(from) = &msg.from else {
+        return Ok(false);
+    };
+    if !is_support_group(msg.chat.id) {
+        return Ok(false);
+    }
+    if bot
+        .get_chat_member(msg.chat.id, from.id)
+        .await?
+        .is_privileged()
+    {
+        return Ok(true);
+    }
+
+    reply_in_group(bot, msg, tr!(config().staff_language, "staff-admin-only")).await?;
+    Ok(false)
+}
+
+/// Customer or team named in the argument of an allowlist command
+enum AllowlistEntry {
+    User(UserId),
+    Team(Team),
+}
+
+/// Reads the argument of `/allow` and `/revoke`: a Telegram user id, or else the name of a team
+fn allowlist_entry(state: &StateContainer, arg: &str) -> Option<AllowlistEntry> {
+    match arg.parse() {
+        Ok(id) => Some(AllowlistEntry::User(UserId(id))),
+        Err(_) => find_team(state, arg).map(AllowlistEntry::Team),
+    }
+}
+
+/// Adds the customer or the team of the command argument to the allowlist or removes it, answering in the group
+async fn update_allowlist(
+    bot: &Bot,
+    state: &StateContainer,
+    msg: &Message,
+    arg: &str,
+    allowed: bool,
+) -> Result<(), teloxide::RequestError> {
+    if !is_support_admin(bot, msg).await? {
+        return Ok(());
+    }
+    let staff_language = config().staff_language;
+    let arg = arg.trim();
+    if arg.is_empty() {
+        return reply_in_group(bot, msg, tr!(staff_language, "staff-allowlist-usage")).await;
+    }
+    let Some(entry) = allowlist_entry(state, arg) else {
+        return reply_in_group(
+            bot,
+            msg,
+            tr!(staff_language, "staff-team-unknown", team = arg),
+        )
+        .await;
+    };
 
-fn create_typeofticket_keyboard(lang: Language) -> (String, InlineKeyboardMarkup) {
-    let (message, buttons) = match lang {
-        Language::Italian => (
-            "Che tipo di supporto ti serve?".to_string(),
-            vec![
-                InlineKeyboardButton::callback("Bug", CALLBACK_BUG),
-                InlineKeyboardButton::callback("Come fare...", CALLBACK_HOW_TO),
-                InlineKeyboardButton::callback("Altro", CALLBACK_OTHER),
-            ],
+    let (result, name) = match &entry {
+        AllowlistEntry::User(user_id) => (
+            state.storage.set_user_allowed(*user_id, allowed),
+            user_id.to_string(),
         ),
-        Language::English => (
-            "What kind of support do you need?".to_string(),
-            vec![
-                InlineKeyboardButton::callback("Bug Report", CALLBACK_BUG),
-                InlineKeyboardButton::callback("How to...", CALLBACK_HOW_TO),
-                InlineKeyboardButton::callback("Other", CALLBACK_OTHER),
-            ],
+        AllowlistEntry::Team(team) => (
+            state.storage.set_team_allowed(team.id, allowed),
+            team.name.clone(),
         ),
     };
+    if let Err(e) = result {
+        eprintln!("Error updating the allowlist for {}: {}", name, e);
+        return Ok(());
+    }
 
-    (
-        message,
-        InlineKeyboardMarkup::new(vec![
-            buttons,
-            vec![InlineKeyboardButton::callback("Cancel", CALLBACK_CANCEL)],
-        ]),
-    )
+    let text = if allowed {
+        tr!(staff_language, "staff-allowlist-added", name = name.clone())
+    } else {
+        tr!(
+            staff_language,
+            "staff-allowlist-removed",
+            name = name.clone()
+        )
+    };
+    reply_in_group(bot, msg, text).await?;
+
+    let (verb, preposition) = if allowed {
+        ("added", "to")
+    } else {
+        ("removed", "from")
+    };
+    println!(
+        "{} {} {} {} {} {} the allowlist",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[ALLOWLIST]".bold().magenta(),
+        get_user_name(&msg.from.clone().unwrap()).bold().blue(),
+        verb,
+        name.bold().cyan(),
+        preposition
+    );
+    Ok(())
 }
 
-/// Handles bot commands
-pub async fn handle_commands(
-    bot: Bot,
-    msg: Message,
-    cmd: Command,
-    state: Arc<StateContainer>,
+/// Whether the customer is banned or muted, so the bot ignores them
+fn is_blocked(state: &StateContainer, user: &User) -> bool {
+    state
+        .storage
+        .active_block(user.id)
+        .map(|block| block.is_some())
+        .unwrap_or_else(|e| {
+            eprintln!("Error loading the ban of {}: {}", user.id, e);
+            false
+        })
+}
+
+/// Customer targeted by a moderation command in a ticket topic: the author of the relayed message the command replies
+/// to, or else the customer who opened the ticket
+fn target_customer(state: &StateContainer, msg: &Message, ticket: &Ticket) -> UserId {
+    replied_message(msg)
+        .and_then(|reply| {
+            state
+                .storage
+                .relayed_message_by_copy(msg.chat.id, reply.id)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error loading the relayed message {}: {}", reply.id, e);
+                    None
+                })
+        })
+        .filter(|relayed| relayed.ticket_id == ticket.id)
+        .and_then(|relayed| relayed.source_user_id)
+        .unwrap_or(ticket.opened_by)
+}
+
+/// Bans the customer targeted in the topic, closing the ticket if they opened it, or mutes them for the `duration`
+async fn block_customer(
+    bot: &Bot,
+    state: &StateContainer,
+    msg: &Message,
+    duration: Option<&str>,
 ) -> Result<(), teloxide::RequestError> {
-    match cmd {
-        Command::Start => {
-            bot.send_message(
-                msg.chat.id,
-                "🤖 Welcome to RustBusters Support Bot! 🛠️\n\nHere are the available commands:\n\n• /support - Open a new support ticket\n  - Choose your language\n  - Select support type\n  - Chat with our group\n\n • /close - Close the current support ticket\n\nHow can we help you today? 😊".to_string(),
+    if !is_support_group(msg.chat.id) || !msg.is_topic_message {
+        return Ok(());
+    }
+    let Some(mut ticket) = msg
+        .thread_id
+        .and_then(|t| state.ticket_by_thread(msg.chat.id, t))
+    else {
+        return Ok(());
+    };
+    let staff_language = config().staff_language;
+    let until = match duration {
+        Some(duration) => {
+            let until = parse_duration(duration)
+                .and_then(|duration| Utc::now().checked_add_signed(duration));
+            if until.is_none() {
+                return reply_in_group(bot, msg, tr!(staff_language, "staff-mute-usage")).await;
+            }
+            until
+        }
+        None => None,
+    };
+
+    let target = target_customer(state, msg, &ticket);
+    let staff = msg.from.clone().unwrap();
+    if let Err(e) = state.storage.block_user(target, until, staff.id) {
+        eprintln!("Error blocking {}: {}", target, e);
+        return Ok(());
+    }
+
+    let profile = state.storage.user_profile(target).unwrap_or_else(|e| {
+        eprintln!("Error loading the profile of {}: {}", target, e);
+        None
+    });
+    let is_opener = target == ticket.opened_by;
+    let user = profile
+        .as_ref()
+        .map(|p| p.display_name.clone())
+        .filter(|name| !name.is_empty())
+        .or_else(|| is_opener.then(|| ticket.opener_name.clone()))
+        .unwrap_or_else(|| target.to_string());
+    // In a customer group everybody sees the notice, otherwise only the blocked customer gets it
+    let in_group = !ticket.chat_id.is_user();
+    let (notice_chat, language) = if in_group {
+        (ticket.chat_id, ticket.language)
+    } else {
+        let language = profile.and_then(|p| p.language).unwrap_or(ticket.language);
+        (ChatId::from(target), language)
+    };
+    if let Some(until) = until {
+        let until = until.format("%Y-%m-%d %H:%M UTC").to_string();
+        reply_in_group(
+            bot,
+            msg,
+            tr!(
+                staff_language,
+                "staff-user-muted",
+                user = user.clone(),
+                until = until.clone()
+            ),
+        )
+        .await?;
+        let notice = if in_group {
+            tr!(
+                language,
+                "user-muted-group",
+                user = user.clone(),
+                until = until.clone()
             )
-            .await?;
+        } else {
+            tr!(language, "user-muted", until = until.clone())
+        };
+        // Abusive customers often blocked the bot already, so failing to tell them is fine
+        if let Err(e) = bot.send_message(notice_chat, notice).await {
+            eprintln!("Error telling {} about the mute: {}", target, e);
         }
-        Command::GetId => {
-            bot.send_message(msg.chat.id, msg.chat.id.0.to_string())
-                .await?;
+
+        println!(
+            "{} {} {} muted {} until {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            "[MUTE]".bold().red(),
+            get_user_name(&staff).bold().blue(),
+            user.bold().yellow(),
+            until
+        );
+    } else {
+        let (staff_notice, notice) = match (is_opener, in_group) {
+            (true, true) => (
+                tr!(staff_language, "staff-user-banned", user = user.clone()),
+                tr!(language, "user-banned-group", user = user.clone()),
+            ),
+            (true, false) => (
+                tr!(staff_language, "staff-user-banned", user = user.clone()),
+                tr!(language, "user-banned"),
+            ),
+            (false, true) => (
+                tr!(staff_language, "staff-member-banned", user = user.clone()),
+                tr!(language, "user-blocked-group", user = user.clone()),
+            ),
+            (false, false) => (
+                tr!(staff_language, "staff-member-banned", user = user.clone()),
+                tr!(language, "user-blocked"),
+            ),
+        };
+        // The others sharing the ticket keep it when the ban hits someone else than the opener
+        if is_opener {
+            close_ticket(bot, state, &mut ticket, CloseReason::Banned).await;
         }
-        Command::Support => {
-            // Verify command was sent in private chat
-            if !matches!(msg.chat.kind, ChatKind::Private(_)) {
-                bot.send_message(msg.chat.id, "This command can only be used in private chat")
-                    .await?;
-                return Ok(());
-            }
 
-            // Check if user already has an open ticket
-            let bindings = state.bindings.lock().await;
-            if bindings.contains_key(&msg.chat.id) {
-                bot.send_message(msg.chat.id, "You already have an open support ticket. Close it with /close or write a new message.")
-                  .await?;
-                return Ok(());
-            }
-            drop(bindings);
+        reply_in_group(bot, msg, staff_notice).await?;
+        if let Err(e) = bot.send_message(notice_chat, notice).await {
+            eprintln!("Error telling {} about the ban: {}", target, e);
+        }
 
-            // Check if there's already a pending request
-            let pending_chat = state.pending_chat.lock().await;
-            if pending_chat.is_some() {
-                bot.send_message(msg.chat.id, "Another support request is being processed. Please wait a moment and try again.")
-                  .await?;
-                println!(
-                    "{} {} {} tried to open a new ticket while another request is pending",
-                    Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                    "[PENDING]".bold().yellow(),
-                    get_user_name(&msg.from.clone().unwrap()).bold().blue()
-                );
-                return Ok(());
-            }
-            drop(pending_chat);
+        println!(
+            "{} {} {} banned {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            "[BAN]".bold().red(),
+            get_user_name(&staff).bold().blue(),
+            user.bold().yellow()
+        );
+    }
 
-            // Send language selection message
-            let keyboard = create_language_keyboard();
-            bot.send_message(
-                msg.chat.id,
-                "Please select your preferred language for support:",
-            )
-            .reply_markup(keyboard)
-            .await?;
-            println!(
-                "{} {} {} started a new ticket",
-                Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                "[NEW]".bold().green(),
-                get_user_name(&msg.from.clone().unwrap()).bold().blue()
-            );
+    Ok(())
+}
+
+/// Lifts the ban or the mute of the user id in the argument, or else of the customer targeted in the topic, even if
+/// its ticket was closed by the ban
+async fn unblock_customer(
+    bot: &Bot,
+    state: &StateContainer,
+    msg: &Message,
+    arg: &str,
+) -> Result<(), teloxide::RequestError> {
+    if !is_support_group(msg.chat.id) {
+        return Ok(());
+    }
+    let staff_language = config().staff_language;
+    let arg = arg.trim();
+    let user_id = if arg.is_empty() {
+        msg.thread_id
+            .filter(|_| msg.is_topic_message)
+            .and_then(|thread_id| {
+                state
+                    .storage
+                    .last_ticket_by_thread(msg.chat.id, thread_id)
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error loading the ticket of topic {}: {}", thread_id, e);
+                        None
+                    })
+            })
+            .map(|ticket| target_customer(state, msg, &ticket))
+    } else {
+        arg.parse().ok().map(UserId)
+    };
+    let Some(user_id) = user_id else {
+        return reply_in_group(bot, msg, tr!(staff_language, "staff-unban-usage")).await;
+    };
+
+    let unblocked = match state.storage.unblock_user(user_id) {
+        Ok(unblocked) => unblocked,
+        Err(e) => {
+            eprintln!("Error unblocking {}: {}", user_id, e);
+            return Ok(());
         }
-        Command::Close => {
-            let mut bindings = state.bindings.lock().await;
-            if let Some(&topic_msg_id) = bindings.get(&msg.chat.id) {
-                // Close topic by replying to the creating message
-                bot.send_message(
-                    support_group_id(),
-                    format!(
-                        "Chat ended by the user {}",
-                        get_user_name(&msg.from.clone().unwrap())
-                    ),
-                )
-                .reply_to(topic_msg_id)
-                .await?;
+    };
+    let profile = state.storage.user_profile(user_id).unwrap_or_else(|e| {
+        eprintln!("Error loading the profile of {}: {}", user_id, e);
+        None
+    });
+    let user = profile
+        .as_ref()
+        .map(|p| p.display_name.clone())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| user_id.to_string());
+    if !unblocked {
+        return reply_in_group(
+            bot,
+            msg,
+            tr!(staff_language, "staff-user-not-blocked", user = user),
+        )
+        .await;
+    }
+    reply_in_group(
+        bot,
+        msg,
+        tr!(staff_language, "staff-user-unbanned", user = user.clone()),
+    )
+    .await?;
 
-                bot.send_message(msg.chat.id, "The support topic has been closed.")
-                    .await?;
+    // The customer may have blocked the bot in the meantime, so failing to tell them is fine
+    let language = profile
+        .and_then(|p| p.language)
+        .unwrap_or(Language::DEFAULT);
+    if let Err(e) = bot
+        .send_message(ChatId::from(user_id), tr!(language, "user-unbanned"))
+        .await
+    {
+        eprintln!("Error telling {} about the unban: {}", user_id, e);
+    }
 
-                bindings.remove(&msg.chat.id);
+    println!(
+        "{} {} {} unbanned {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[BAN]".bold().green(),
+        get_user_name(&msg.from.clone().unwrap()).bold().blue(),
+        user.bold().yellow()
+    );
+    Ok(())
+}
 
-                // Salva i bindings dopo la rimozione
-                drop(bindings);
-                if let Err(e) = state.save_bindings().await {
-                    eprintln!("Errore nel salvare i bindings: {}", e);
-                }
+/// Relays a message of a customer to the topic of their ticket, or takes it as the answer to the current question
+/// of the ticket creation
+async fn relay_customer_message(
+    bot: &Bot,
+    state: &Arc<StateContainer>,
+    msg: &Message,
+) -> Result<(), teloxide::RequestError> {
+    let ticket = customer_ticket(state, msg);
+    // A team member opening a ticket of their own answers its questions, even while a ticket of the team is open
+    let shared = ticket.as_ref().is_some_and(|t| t.chat_id != msg.chat.id);
+    if shared && state.pending.lock().await.contains_key(&msg.chat.id) {
+        return answer_intake_question(bot, state, msg).await;
+    }
 
-                println!(
-                    "{} {} {} closed the ticket!",
-                    Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                    "[CLOSE]".bold().red(),
-                    get_user_name(&msg.from.clone().unwrap()).bold().yellow()
-                )
-            } else if msg.chat.id == support_group_id() {
-                if let Some(reply_to) = msg.reply_to_message() {
-                    if let Some((&private_chat_id, _)) =
-                        bindings.iter().find(|(_, &msg_id)| msg_id == reply_to.id)
-                    {
-                        bot.send_message(support_group_id(), "Chat ended")
-                            .reply_to(reply_to.id)
-                            .await?;
-                        bot.send_message(private_chat_id, "RustBusters closed the support chat. Write /support to open a new one.")
-                          .await?;
-                        println!(
-                            "{} {} {} closed the ticket!",
-                            Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                            "[CLOSE - RB]".bold().red(),
-                            get_user_name(&msg.from.clone().unwrap()).bold().blue()
-                        )
-                    }
-                }
+    if let Some(mut ticket) = ticket {
+        // A message of the customer brings the ticket back to RustBusters, reopening it if resolved
+        set_ticket_status(bot, state, &mut ticket, TicketStatus::Open).await;
 
-                bindings.retain(|_, &mut msg_id| msg_id != msg.id);
-            }
+        if msg.media_group_id().is_some() {
+            let (group_id, thread_id) = (ticket.group_id, Some(ticket.thread_id));
+            let author = team_author(&ticket, msg.from.as_ref());
+            buffer_album(bot, state, msg, ticket, vec![group_id], thread_id, author).await;
+            return Ok(());
+        }
+
+        // Over the rate limit the messages wait in a queue, relayed as the limits allow
+        if queue_over_limit(bot, state, &ticket, msg).await? {
+            return Ok(());
+        }
+        relay_to_topic(bot, state, &ticket, msg).await?;
+    } else {
+        answer_intake_question(bot, state, msg).await?;
+    }
+
+    Ok(())
+}
+
+/// Relays a message of a customer to the topic of the ticket
+async fn relay_to_topic(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &Ticket,
+    msg: &Message,
+) -> Result<(), teloxide::RequestError> {
+    let reply_to = relayed_reply_target(state, msg, ticket.group_id);
+    let author = team_author(ticket, msg.from.as_ref());
+    if let Some(copy) = relay_message(
+        bot,
+        msg,
+        ticket.group_id,
+        Some(ticket.thread_id),
+        reply_to,
+        author.as_deref(),
+    )
+    .await?
+    {
+        remember_relayed_message(state, ticket, msg, &copy);
+        if let Err(e) = state.storage.touch_ticket(ticket.id) {
+            eprintln!("Error updating ticket #{}: {}", ticket.id, e);
+        }
+
+        println!(
+            "{} {} {} sent a message -> {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            "[MESSAGE]".bold().blue(),
+            get_user_name(&msg.from.clone().unwrap()).bold().yellow(),
+            describe_message(msg).italic()
+        );
+    }
+
+    Ok(())
+}
+
+/// Queues the message of the customer when they or the support group of the ticket are over the rate limit, or when
+/// their older messages are still waiting. The customer is warned when the queue starts.
+/// Returns whether the message was queued.
+async fn queue_over_limit(
+    bot: &Bot,
+    state: &Arc<StateContainer>,
+    ticket: &Ticket,
+    msg: &Message,
+) -> Result<bool, teloxide::RequestError> {
+    let Some(from) = &msg.from else {
+        return Ok(false);
+    };
+
+    let key = (msg.chat.id, from.id);
+    let mut floods = state.floods.lock().await;
+    if let Some(queue) = floods.get_mut(&key) {
+        queue.push(msg.clone());
+        return Ok(true);
+    }
+    let Err(wait) = state.rate_limiter.acquire(from.id, ticket.group_id) else {
+        return Ok(false);
+    };
+    floods.insert(key, vec![msg.clone()]);
+    drop(floods);
+
+    tokio::spawn(relay_flood(
+        bot.clone(),
+        state.clone(),
+        key,
+        ticket.group_id,
+        wait,
+    ));
+
+    println!(
+        "{} {} {} is over the rate limit, queuing their messages",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[FLOOD]".bold().red(),
+        get_user_name(from).bold().yellow()
+    );
+    bot.send_message(msg.chat.id, tr!(ticket.language, "rate-limited"))
+        .await?;
+    Ok(true)
+}
+
+/// Relays the queue of the messages of the customer over the rate limit in a chat, as the limits allow.
+/// The texts waiting one after the other are coalesced into a single message.
+async fn relay_flood(
+    bot: Bot,
+    state: Arc<StateContainer>,
+    (chat_id, user_id): (ChatId, UserId),
+    group_id: ChatId,
+    mut wait: Duration,
+) {
+    loop {
+        tokio::time::sleep(wait).await;
+
+        let mut floods = state.floods.lock().await;
+        if floods.get(&(chat_id, user_id)).is_none_or(Vec::is_empty) {
+            floods.remove(&(chat_id, user_id));
+            return;
+        }
+        if let Err(next) = state.rate_limiter.acquire(user_id, group_id) {
+            wait = next;
+            continue;
+        }
+        wait = Duration::ZERO;
+        let batch = next_flood_batch(floods.entry((chat_id, user_id)).or_default());
+        drop(floods);
+
+        if let Err(e) = relay_flood_batch(&bot, &state, &batch).await {
+            eprintln!("Error relaying the queued messages of {}: {}", user_id, e);
+        }
+    }
+}
+
+/// Takes the next messages to relay from the queue: the texts at its start that fit in a single message, or else
+/// its first message
+fn next_flood_batch(queue: &mut Vec<Message>) -> Vec<Message> {
+    let mut length = 0;
+    let texts = queue
+        .iter()
+        .take_while(|msg| {
+            let Some(text) = msg.text() else {
+                return false;
+            };
+            length += text.encode_utf16().count() + 1;
+            // Some room is left for the name of the author
+            length <= MAX_TEXT_LENGTH - 100
+        })
+        .count();
+    queue.drain(..texts.max(1)).collect()
+}
+
+/// Relays messages taken from the queue of a customer to the topic of their ticket, coalescing them if more than one
+async fn relay_flood_batch(
+    bot: &Bot,
+    state: &StateContainer,
+    batch: &[Message],
+) -> Result<(), teloxide::RequestError> {
+    // The ticket may have been closed while the messages were waiting
+    let Some(ticket) = customer_ticket(state, &batch[0]) else {
+        return Ok(());
+    };
+    if batch.len() == 1 {
+        return relay_to_topic(bot, state, &ticket, &batch[0]).await;
+    }
+
+    let author = team_author(&ticket, batch[0].from.as_ref());
+    let copy = relay_texts(
+        bot,
+        batch,
+        ticket.group_id,
+        Some(ticket.thread_id),
+        author.as_deref(),
+    )
+    .await?;
+    // Every message of the batch leads to the copy, for the replies and the edits
+    for msg in batch {
+        if let Err(e) = state.storage.save_relayed_message(&RelayedMessage {
+            ticket_id: ticket.id,
+            source_chat_id: msg.chat.id,
+            source_message_id: msg.id,
+            source_user_id: msg.from.as_ref().map(|from| from.id),
+            copy_chat_id: copy.chat.id,
+            copy_message_id: copy.id,
+            coalesced: true,
+        }) {
+            eprintln!("Error saving the copy of message {}: {}", msg.id, e);
         }
     }
+    if let Err(e) = state.storage.touch_ticket(ticket.id) {
+        eprintln!("Error updating ticket #{}: {}", ticket.id, e);
+    }
+
+    println!(
+        "{} {} {} sent {} messages, relayed together",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[FLOOD]".bold().blue(),
+        get_user_name(&batch[0].from.clone().unwrap())
+            .bold()
+            .yellow(),
+        batch.len()
+    );
     Ok(())
 }
 
+/// Whether a message belongs to an album being collected. Only the item carrying the caption mentions the bot, the
+/// others in the groups of the customers follow it.
+async fn is_buffered_album_item(state: &StateContainer, msg: &Message) -> bool {
+    match msg.media_group_id() {
+        Some(group_id) => state.albums.lock().await.contains_key(group_id),
+        None => false,
+    }
+}
+
+/// Whether a message of a customer group is meant for the bot: it mentions the bot or replies to one of its messages
+fn is_addressed_to_bot(msg: &Message, me: &Me) -> bool {
+    let replies_to_bot = msg
+        .reply_to_message()
+        .and_then(|reply| reply.from.as_ref())
+        .is_some_and(|user| user.id == me.id);
+    if replies_to_bot {
+        return true;
+    }
+
+    let mention = format!("@{}", me.username());
+    msg.parse_entities()
+        .or_else(|| msg.parse_caption_entities())
+        .is_some_and(|entities| {
+            entities.iter().any(|entity| match entity.kind() {
+                MessageEntityKind::Mention => entity.text().eq_ignore_ascii_case(&mention),
+                MessageEntityKind::TextMention { user } => user.id == me.id,
+                _ => false,
+            })
+        })
+}
+
 /// Handles regular messages
 pub async fn handle_messages(
     bot: Bot,
     msg: Message,
     state: Arc<StateContainer>,
+    me: Me,
 ) -> Result<(), teloxide::RequestError> {
-    // Handle topic creation
-    if let MessageKind::ForumTopicCreated(_) = &msg.kind {
-        // Verify if the topic was created by the bot
-        if let Some(from) = &msg.from {
-            if from.is_bot {
-                let mut pending_chat = state.pending_chat.lock().await;
-                if let Some((chat_id, language, Some(ticket_type))) = *pending_chat {
-                    // Save the binding
-                    let mut bindings = state.bindings.lock().await;
-                    bindings.insert(chat_id, msg.id);
-
-                    // Salva i bindings dopo la rimozione
-                    drop(bindings);
-                    if let Err(e) = state.save_bindings().await {
-                        eprintln!("Errore nel salvare i bindings: {}", e);
-                    }
-
-                    // Clear pending_chat
-                    *pending_chat = None;
-
-                    // Send confirmation message with ticket type
-                    let confirmation = match language {
-                        Language::Italian => format!(
-                            "Ticket di supporto creato per *_{}_*\\! Puoi ora chattare con RustBusters attraverso questo bot\\.\nPer chiudere la chat, usa /close\\.",
-                            ticket_type.to_string().replace("...","\\.\\.\\.")
-                        ),
-                        Language::English => format!(
-                            "Support ticket created for *_{}_*\\! You can now chat with RustBusters through this bot\\.\nTo close the chat, use /close\\.",
-                            ticket_type.to_string().replace("...","\\.\\.\\.")
-                        ),
-                    };
-                    bot.send_message(chat_id, confirmation)
-                        .parse_mode(ParseMode::MarkdownV2)
-                        .await?;
-                }
-            }
+    // Banned and muted customers are ignored
+    if let Some(from) = msg.from.as_ref().filter(|_| is_customer_chat(&msg.chat)) {
+        if is_blocked(&state, from) {
+            return Ok(());
         }
-        return Ok(());
     }
 
-    let bindings = state.bindings.lock().await;
-
     match msg.chat.kind {
-        // Handle private chat messages
-        ChatKind::Private(_) => {
-            if let Some(&topic_msg_id) = bindings.get(&msg.chat.id) {
-                if let Some(text) = msg.text() {
-                    bot.send_message(support_group_id(), text)
-                        .reply_to(topic_msg_id)
-                        .await?;
-                    println!(
-                        "{} {} {} sent a message -> {}",
-                        Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                        "[MESSAGE]".bold().blue(),
-                        get_user_name(&msg.from.clone().unwrap()).bold().yellow(),
-                        text.italic()
-                    );
-                }
+        // Handle the messages of the customers
+        ChatKind::Private(_) => relay_customer_message(&bot, &state, &msg).await?,
+        // In the groups of the customers only the messages mentioning the bot or replying to it are for the support
+        ChatKind::Public(_) if is_customer_chat(&msg.chat) => {
+            if is_addressed_to_bot(&msg, &me) || is_buffered_album_item(&state, &msg).await {
+                relay_customer_message(&bot, &state, &msg).await?;
             }
         }
         // Handle forum messages
         ChatKind::Public(_) => {
-            if msg.chat.id == support_group_id() {
-                if let Some(reply_to) = msg.reply_to_message() {
-                    if let Some((&private_chat_id, _)) =
-                        bindings.iter().find(|(_, &msg_id)| msg_id == reply_to.id)
+            if is_support_group(msg.chat.id) {
+                remember_staff_member(&state, &msg);
+            }
+
+            if is_support_group(msg.chat.id) && msg.is_topic_message {
+                if let Some(mut ticket) = msg
+                    .thread_id
+                    .and_then(|t| state.ticket_by_thread(msg.chat.id, t))
+                {
+                    // Internal notes stay in the topic
+                    if let Some(text) = note_text(&msg) {
+                        return save_note(&bot, &state, &msg, &ticket, text).await;
+                    }
+
+                    if msg.media_group_id().is_some() {
+                        if ticket.status == TicketStatus::Open {
+                            set_ticket_status(
+                                &bot,
+                                &state,
+                                &mut ticket,
+                                TicketStatus::WaitingOnCustomer,
+                            )
+                            .await;
+                        }
+                        let recipients = ticket_recipients(&state, &ticket);
+                        buffer_album(&bot, &state, &msg, ticket, recipients, None, None).await;
+                        return Ok(());
+                    }
+
+                    let reply_to = relayed_reply_target(&state, &msg, ticket.chat_id);
+                    if let Some(copy) =
+                        relay_message(&bot, &msg, ticket.chat_id, None, reply_to, None).await?
                     {
-                        if let Some(text) = msg.text() {
-                            bot.send_message(private_chat_id, text).await?;
-                            println!(
-                                "{} {} {} sent a message -> {}",
-                                Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                                "[MESSAGE - RB]".bold().blue(),
-                                get_user_name(&msg.from.clone().unwrap()).bold().blue(),
-                                text.italic()
-                            );
+                        remember_relayed_message(&state, &ticket, &msg, &copy);
+                        relay_to_team_members(&bot, &state, &ticket, &msg).await;
+                        if ticket.status == TicketStatus::Open {
+                            set_ticket_status(
+                                &bot,
+                                &state,
+                                &mut ticket,
+                                TicketStatus::WaitingOnCustomer,
+                            )
+                            .await;
+                        }
+                        if let Err(e) = state.storage.touch_ticket(ticket.id) {
+                            eprintln!("Error updating ticket #{}: {}", ticket.id, e);
                         }
+
+                        println!(
+                            "{} {} {} sent a message -> {}",
+                            Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                            "[MESSAGE - RB]".bold().blue(),
+                            get_user_name(&msg.from.clone().unwrap()).bold().blue(),
+                            describe_message(&msg).italic()
+                        );
                     }
                 }
             }
@@ -275,82 +2214,255 @@ pub async fn handle_messages(
     Ok(())
 }
 
+/// Handles edited messages, applying the edit to the relayed copy
+pub async fn handle_edited_messages(
+    bot: Bot,
+    msg: Message,
+    state: Arc<StateContainer>,
+) -> Result<(), teloxide::RequestError> {
+    // Edits of banned or muted customers are dropped like their messages
+    if let Some(from) = msg.from.as_ref().filter(|_| is_customer_chat(&msg.chat)) {
+        if is_blocked(&state, from) {
+            return Ok(());
+        }
+    }
+
+    // Edits of a message that was never relayed (commands, notes...) are ignored
+    let copies = state
+        .storage
+        .relayed_copies(msg.chat.id, msg.id)
+        .unwrap_or_else(|e| {
+            eprintln!("Error loading the copies of message {}: {}", msg.id, e);
+            Vec::new()
+        });
+    let Some(ticket_id) = copies.first().map(|r| r.ticket_id) else {
+        return Ok(());
+    };
+
+    let from_group = is_support_group(msg.chat.id);
+    let ticket = if from_group {
+        // A message turned into an internal note must not reach the customer
+        if note_text(&msg).is_some() {
+            return Ok(());
+        }
+        msg.thread_id
+            .and_then(|t| state.ticket_by_thread(msg.chat.id, t))
+    } else {
+        // The ticket of the customer, or one of the tickets of their team
+        state
+            .ticket_by_chat(msg.chat.id)
+            .filter(|t| t.id == ticket_id)
+            .or_else(|| {
+                msg.from.as_ref().and_then(|from| {
+                    team_tickets(&state, from)
+                        .into_iter()
+                        .find(|t| t.id == ticket_id)
+                })
+            })
+    };
+    let Some(ticket) = ticket.filter(|t| t.id == ticket_id) else {
+        return Ok(());
+    };
+    let author = if from_group {
+        None
+    } else {
+        team_author(&ticket, msg.from.as_ref())
+    };
+
+    // The first copy goes to the customer who opened the ticket, the other ones to their team
+    for (index, relayed) in copies.iter().enumerate() {
+        let result = edit_copy(&bot, &state, &ticket, &msg, relayed, author.as_deref()).await;
+        fan_out_result(index, relayed.copy_chat_id, "relaying the edit", result)?;
+    }
+
+    println!(
+        "{} {} {} edited a message -> {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[EDIT]".bold().blue(),
+        get_user_name(&msg.from.clone().unwrap()).bold().yellow(),
+        describe_message(&msg).italic()
+    );
+
+    Ok(())
+}
+
+/// Applies the edit of `msg` to one of its copies. When the copy can't be edited, the edited message is sent again.
+async fn edit_copy(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &Ticket,
+    msg: &Message,
+    relayed: &RelayedMessage,
+    author: Option<&str>,
+) -> Result<(), teloxide::RequestError> {
+    // Editing a copy holding several messages would drop the other ones
+    if !relayed.coalesced {
+        let Err(e) = relay_edit(
+            bot,
+            msg,
+            relayed.copy_chat_id,
+            relayed.copy_message_id,
+            author,
+        )
+        .await
+        else {
+            return Ok(());
+        };
+        eprintln!(
+            "Error editing the copy of message {}, sending it again: {}",
+            msg.id, e
+        );
+    }
+
+    // Send the edited message again, right after a note replying to the stale copy
+    let to_group = relayed.copy_chat_id == ticket.group_id;
+    let thread_id = to_group.then_some(ticket.thread_id);
+    let language = if to_group {
+        config().staff_language
+    } else {
+        ticket.language
+    };
+    let mut notice = bot
+        .send_message(relayed.copy_chat_id, tr!(language, "message-edited"))
+        .reply_parameters(ReplyParameters::new(relayed.copy_message_id));
+    if let Some(thread_id) = thread_id {
+        notice = notice.message_thread_id(thread_id);
+    }
+    notice.await?;
+
+    if let Some(copy) =
+        relay_message(bot, msg, relayed.copy_chat_id, thread_id, None, author).await?
+    {
+        remember_relayed_message(state, ticket, msg, &copy);
+    }
+
+    Ok(())
+}
+
 /// Handles callback queries (inline keyboard buttons)
 pub async fn handle_callback_query(
     bot: Bot,
     query: CallbackQuery,
     state: Arc<StateContainer>,
 ) -> Result<(), teloxide::RequestError> {
+    // Banned and muted customers can't use the buttons either
+    if is_blocked(&state, &query.from) {
+        bot.answer_callback_query(&query.id).await?;
+        return Ok(());
+    }
+
     if let (Some(data), Some(message), from) = (&query.data, &query.message, &query.from) {
         match data.as_str() {
             // Handle language selection
-            CALLBACK_ITALIAN | CALLBACK_ENGLISH => {
-                let language = if data.as_str() == CALLBACK_ITALIAN {
-                    Language::Italian
-                } else {
-                    Language::English
-                };
+            data if data.starts_with(CALLBACK_LANGUAGE) => {
+                if let Some(language) = Language::from_code(&data[CALLBACK_LANGUAGE.len()..]) {
+                    // In the groups the language is chosen for the ticket, not for the user
+                    let chat_id = message.chat().id;
+                    if chat_id.is_user() {
+                        save_user_language(&state, from, language);
+                    }
 
-                // Store the user and selected language
-                let mut pending_chat = state.pending_chat.lock().await;
-                *pending_chat = Some((message.chat().id, language, None));
-                drop(pending_chat);
+                    // Store the selected language in the chat's wizard
+                    let mut pending = state
+                        .pending
+                        .lock()
+                        .await
+                        .remove(&chat_id)
+                        .unwrap_or_default();
+                    pending.language = Some(language);
 
-                // Delete the language selection message
-                bot.delete_message(message.chat().id, message.id()).await?;
+                    // Delete the language selection message
+                    bot.delete_message(chat_id, message.id()).await?;
 
-                // Send ticket type selection
-                let (prompt, keyboard) = create_typeofticket_keyboard(language);
-                bot.send_message(message.chat().id, prompt)
-                    .reply_markup(keyboard)
-                    .await?;
+                    ask_team_or_category(&bot, &state, chat_id, from, language, pending).await?;
+                }
             }
+            // Handle the change of the preferred language
+            data if data.starts_with(CALLBACK_PREFERENCE) => {
+                if let Some(language) = Language::from_code(&data[CALLBACK_PREFERENCE.len()..]) {
+                    save_user_language(&state, from, language);
 
-            // Handle ticket type selection
-            CALLBACK_BUG | CALLBACK_HOW_TO | CALLBACK_OTHER => {
-                let ticket_type = match data.as_str() {
-                    CALLBACK_BUG => TicketType::Bug,
-                    CALLBACK_HOW_TO => TicketType::HowTo,
-                    CALLBACK_OTHER => TicketType::Other,
-                    _ => return Ok(()),
-                };
-
-                let mut pending_chat = state.pending_chat.lock().await;
-                if let Some((chat_id, language, _)) = *pending_chat {
-                    // Update pending chat with ticket type
-                    *pending_chat = Some((chat_id, language, Some(ticket_type)));
-
-                    // Delete the ticket type selection message
-                    bot.delete_message(message.chat().id, message.id()).await?;
-
-                    // Create topic with type in the name
-                    let type_str = ticket_type.to_string();
-                    let topic_name = format!(
-                        "{} {} - {}",
-                        language.to_flag(),
-                        type_str,
-                        get_user_name(from)
-                    );
+                    // The open ticket continues in the new language
+                    if let Some(mut ticket) = state.ticket_by_chat(message.chat().id) {
+                        set_ticket_language(&bot, &state, &mut ticket, language).await;
+                    }
 
-                    bot.create_forum_topic(
-                        support_group_id(),
-                        &topic_name,
-                        get_random_topic_color(),
-                        "New support ticket",
+                    bot.edit_message_text(
+                        message.chat().id,
+                        message.id(),
+                        tr!(language, "language-changed"),
                     )
                     .await?;
+
                     println!(
-                        "{} {} {} created a new ticket of type {}",
-                        Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                        "[NEW]".bold().yellow(),
+                        "{} {} {} changed language to {}",
+                        Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        "[LANGUAGE]".bold().cyan(),
                         get_user_name(from).bold().blue(),
-                        type_str.bold().cyan()
+                        language.code().bold()
                     );
                 }
             }
+            CALLBACK_INTAKE_SKIP | CALLBACK_INTAKE_BACK => {
+                let chat_id = message.chat().id;
+
+                // Only the buttons of the current question are valid
+                let mut pending_map = state.pending.lock().await;
+                let pending = if pending_map
+                    .get(&chat_id)
+                    .is_some_and(|p| p.question_message == Some(message.id()))
+                {
+                    pending_map.remove(&chat_id)
+                } else {
+                    None
+                };
+                drop(pending_map);
+
+                if let Some((mut pending, language, category)) = pending.and_then(|p| {
+                    let language = p.language?;
+                    let category = find_category(p.category.as_deref()?)?;
+                    Some((p, language, category))
+                }) {
+                    if data.as_str() == CALLBACK_INTAKE_SKIP {
+                        pending.answers.push(None);
+                    } else {
+                        pending.answers.pop();
+                    }
+                    next_intake_step(&bot, &state, chat_id, from, language, category, pending)
+                        .await?;
+                }
+            }
+            CALLBACK_TEAM_SKIP => {
+                let chat_id = message.chat().id;
+
+                // Only the buttons of the current question are valid
+                let mut pending_map = state.pending.lock().await;
+                let pending = if pending_map
+                    .get(&chat_id)
+                    .is_some_and(|p| p.team_question == Some(message.id()))
+                {
+                    pending_map.remove(&chat_id)
+                } else {
+                    None
+                };
+                drop(pending_map);
+
+                if let Some((mut pending, language)) =
+                    pending.and_then(|p| p.language.map(|language| (p, language)))
+                {
+                    pending.team_question = None;
+                    bot.edit_message_reply_markup(chat_id, message.id()).await?;
+                    ask_team_or_category(&bot, &state, chat_id, from, language, pending).await?;
+                }
+            }
+            CALLBACK_REOPEN => {
+                // Remove the button, the ticket can be reopened only once from here
+                bot.edit_message_reply_markup(message.chat().id, message.id())
+                    .await?;
+                reopen_ticket(&bot, &state, message.chat().id, from).await?;
+            }
             CALLBACK_CANCEL => {
-                let mut pending_chat = state.pending_chat.lock().await;
-                *pending_chat = None;
+                state.pending.lock().await.remove(&message.chat().id);
                 bot.delete_message(message.chat().id, message.id()).await?;
                 println!(
                     "{} {} {} cancelled the ticket creation",
@@ -359,6 +2471,54 @@ pub async fn handle_callback_query(
                     get_user_name(from).bold().blue()
                 );
             }
+            // Handle category selection
+            data if data.starts_with(CALLBACK_CATEGORY) => {
+                let category = find_category(&data[CALLBACK_CATEGORY.len()..]);
+                let chat_id = message.chat().id;
+
+                // The categories changed since the keyboard was sent, so the current ones are shown instead
+                if category.is_none() {
+                    let language = state
+                        .pending
+                        .lock()
+                        .await
+                        .get(&chat_id)
+                        .and_then(|p| p.language);
+                    if let Some(language) = language {
+                        bot.answer_callback_query(&query.id)
+                            .text(tr!(language, "category-unknown"))
+                            .await?;
+                        let (prompt, keyboard) = create_category_keyboard(language);
+                        bot.edit_message_text(chat_id, message.id(), prompt)
+                            .reply_markup(keyboard)
+                            .await?;
+                        return Ok(());
+                    }
+                }
+
+                // Take the wizard out of the pending map, so repeated clicks are ignored
+                let pending = state.pending.lock().await.remove(&message.chat().id);
+                if let (
+                    Some(category),
+                    Some(PendingTicket {
+                        language: Some(language),
+                        ..
+                    }),
+                ) = (category, pending)
+                {
+                    // Delete the category selection message
+                    bot.delete_message(chat_id, message.id()).await?;
+
+                    // Fill the intake form of the category first, if any
+                    let pending = PendingTicket {
+                        language: Some(language),
+                        category: Some(category.id.clone()),
+                        ..PendingTicket::default()
+                    };
+                    next_intake_step(&bot, &state, chat_id, from, language, category, pending)
+                        .await?;
+                }
+            }
             _ => (),
         }
     }
@@ -0,0 +1,147 @@
+// i18n.rs
+use crate::state::Language;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+// Generated by build.rs from the files in the `locales` directory
+include!(concat!(env!("OUT_DIR"), "/locales.rs"));
+
+/// Messages of the language, in the Fluent syntax
+fn locale_source(language: Language) -> &'static str {
+    LOCALES
+        .iter()
+        .find(|(code, _)| *code == language.code())
+        .map(|(_, source)| *source)
+        .expect("Every language has a locale file")
+}
+
+fn load_bundle(language: Language) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(locale_source(language).to_string())
+        .unwrap_or_else(|(_, errors)| panic!("Invalid {} locale: {:?}", language.code(), errors));
+    let langid: LanguageIdentifier = language.code().parse().expect("Invalid language code");
+
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Unicode isolation marks around the arguments would show up in Telegram
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .unwrap_or_else(|errors| panic!("Invalid {} locale: {:?}", language.code(), errors));
+    bundle
+}
+
+fn bundle(language: Language) -> &'static FluentBundle<FluentResource> {
+    static BUNDLES: OnceLock<HashMap<Language, FluentBundle<FluentResource>>> = OnceLock::new();
+    &BUNDLES.get_or_init(|| {
+        Language::all()
+            .map(|language| (language, load_bundle(language)))
+            .collect()
+    })[&language]
+}
+
+/// Message `key` in the language, with its arguments. Missing keys are logged and replaced by the key itself.
+/// Use the [`tr!`] macro instead of calling this directly.
+pub fn translate(language: Language, key: &str, args: Option<&FluentArgs>) -> String {
+    let bundle = bundle(language);
+    let Some(pattern) = bundle.get_message(key).and_then(|message| message.value()) else {
+        eprintln!("Missing message {} in the {} locale", key, language.code());
+        return key.to_string();
+    };
+
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        eprintln!(
+            "Error formatting message {} in the {} locale: {:?}",
+            key,
+            language.code(),
+            errors
+        );
+    }
+    text.into_owned()
+}
+
+/// Looks up a message of the locale files: `tr!(language, "key")` or `tr!(language, "key", name = value, ...)`
+macro_rules! tr {
+    ($language:expr, $key:expr) => {
+        $crate::i18n::translate($language, $key, None)
+    };
+    ($language:expr, $key:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = ::fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::translate($language, $key, Some(&args))
+    }};
+}
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluent_syntax::ast::Entry;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::Path;
+
+    /// Keys of the messages defined in the locale file of the language
+    fn locale_keys(language: Language) -> BTreeSet<String> {
+        let resource = FluentResource::try_new(locale_source(language).to_string()).unwrap_or_else(
+            |(_, errors)| panic!("Invalid {} locale: {:?}", language.code(), errors),
+        );
+        resource
+            .entries()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Keys looked up with `tr!` in the sources
+    fn used_keys() -> BTreeSet<String> {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut keys = BTreeSet::new();
+        for entry in fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "rs") || path.ends_with("i18n.rs") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            // The key is the first string literal of each call, skipping macros like `include_str!`
+            for (start, _) in source.match_indices("tr!(") {
+                if source[..start].ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                    continue;
+                }
+                if let Some(key) = source[start..].split('"').nth(1) {
+                    keys.insert(key.to_string());
+                }
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn every_key_is_in_every_language() {
+        assert!(
+            Language::from_code(Language::DEFAULT.code()).is_some(),
+            "The default language needs a locale file"
+        );
+
+        let all_keys: BTreeSet<String> = Language::all()
+            .flat_map(locale_keys)
+            .chain(used_keys())
+            .collect();
+
+        for language in Language::all() {
+            let keys = locale_keys(language);
+            let missing: Vec<&String> = all_keys.difference(&keys).collect();
+            assert!(
+                missing.is_empty(),
+                "Missing keys in the {} locale: {:?}",
+                language.code(),
+                missing
+            );
+        }
+    }
+}
@@ -1,10 +1,22 @@
 // main.rs
+mod category;
 mod commands;
+mod config;
 mod handlers;
+mod i18n;
+mod ratelimit;
+mod relay;
+mod routing;
+mod scheduler;
 mod state;
+mod storage;
+mod ticket;
 mod util;
 
-use crate::handlers::{handle_callback_query, handle_commands, handle_messages};
+use crate::handlers::{
+    handle_callback_query, handle_commands, handle_edited_messages, handle_messages,
+};
+use crate::scheduler::spawn_inactivity_scheduler;
 use crate::state::StateContainer;
 use dotenv::dotenv;
 use std::sync::Arc;
@@ -18,6 +30,9 @@ async fn main() {
     // Initialize application state
     let state = Arc::new(StateContainer::new());
 
+    // Start the background jobs
+    spawn_inactivity_scheduler(bot.clone(), state.clone());
+
     // Setup dependency injection
     let mut deps = DependencyMap::new();
     deps.insert(state);
@@ -33,6 +48,7 @@ async fn main() {
                 )
                 .branch(dptree::entry().endpoint(handle_messages)),
         )
+        .branch(Update::filter_edited_message().endpoint(handle_edited_messages))
         .branch(Update::filter_callback_query().endpoint(handle_callback_query));
 
     // Build and launch the dispatcher
@@ -0,0 +1,211 @@
+// ratelimit.rs
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use teloxide::types::{ChatId, UserId};
+
+/// Size and refill rate of the token buckets of a kind
+#[derive(Clone, Copy)]
+pub struct Limit {
+    /// Messages that can be relayed at once after a quiet period
+    pub burst: u32,
+    /// Messages relayed per minute once the burst is over
+    pub per_minute: u32,
+}
+
+impl Limit {
+    fn capacity(self) -> f64 {
+        f64::from(self.burst)
+    }
+
+    fn tokens_per_second(self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+/// Who a token bucket limits
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum BucketKey {
+    /// A customer, whatever the chat they write from
+    User(UserId),
+    /// A chat the messages are relayed to, for the limits of Telegram on the messages sent to a group
+    Destination(ChatId),
+}
+
+/// Tokens left in a bucket, refilled over time up to the capacity. Each relayed message takes one.
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, limit: Limit, now: Instant) {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.tokens_per_second()).min(limit.capacity());
+        self.refilled_at = now;
+    }
+
+    /// How long until the bucket has a whole token
+    fn wait_time(&self, limit: Limit) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / limit.tokens_per_second())
+    }
+}
+
+/// Token bucket rate limiter of the messages relayed to the support, for each customer and each destination chat
+pub struct RateLimiter {
+    user_limit: Limit,
+    destination_limit: Limit,
+    buckets: Mutex<HashMap<BucketKey, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(user_limit: Limit, destination_limit: Limit) -> Self {
+        Self {
+            user_limit,
+            destination_limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token of the customer and one of the destination, only if both have one.
+    /// Otherwise returns how long to wait before trying again.
+    pub fn acquire(&self, user: UserId, destination: ChatId) -> Result<(), Duration> {
+        self.acquire_at(user, destination, Instant::now())
+    }
+
+    fn acquire_at(&self, user: UserId, destination: ChatId, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        // Full buckets are dropped, they are the same as a missing one
+        buckets.retain(|key, bucket| {
+            bucket.refill(self.limit(*key), now);
+            bucket.tokens < self.limit(*key).capacity()
+        });
+
+        let keys = [BucketKey::User(user), BucketKey::Destination(destination)];
+
+        let wait = keys
+            .iter()
+            .filter_map(|key| Some(buckets.get(key)?.wait_time(self.limit(*key))))
+            .max()
+            .unwrap_or_default();
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        for key in keys {
+            let limit = self.limit(key);
+            buckets
+                .entry(key)
+                .or_insert(TokenBucket {
+                    tokens: limit.capacity(),
+                    refilled_at: now,
+                })
+                .tokens -= 1.0;
+        }
+        Ok(())
+    }
+
+    fn limit(&self, key: BucketKey) -> Limit {
+        match key {
+            BucketKey::User(_) => self.user_limit,
+            BucketKey::Destination(_) => self.destination_limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: UserId = UserId(1);
+    const OTHER_USER: UserId = UserId(2);
+    const GROUP: ChatId = ChatId(-100);
+
+    /// Limit of the group high enough to never be reached
+    const UNLIMITED: Limit = Limit {
+        burst: 1000,
+        per_minute: 1000,
+    };
+
+    fn user_limiter(burst: u32, per_minute: u32) -> RateLimiter {
+        RateLimiter::new(Limit { burst, per_minute }, UNLIMITED)
+    }
+
+    #[test]
+    fn exhausts_the_burst() {
+        let limiter = user_limiter(3, 6);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.acquire_at(USER, GROUP, now), Ok(()));
+        }
+        assert!(limiter.acquire_at(USER, GROUP, now).is_err());
+        // The other customers have their own bucket
+        assert_eq!(limiter.acquire_at(OTHER_USER, GROUP, now), Ok(()));
+    }
+
+    #[test]
+    fn returns_the_wait_until_the_next_token() {
+        // A token every 10 seconds
+        let limiter = user_limiter(1, 6);
+        let now = Instant::now();
+        assert_eq!(limiter.acquire_at(USER, GROUP, now), Ok(()));
+        let wait = limiter.acquire_at(USER, GROUP, now).unwrap_err();
+        assert!(wait.abs_diff(Duration::from_secs(10)) < Duration::from_millis(1));
+
+        let later = now + Duration::from_secs(4);
+        let wait = limiter.acquire_at(USER, GROUP, later).unwrap_err();
+        assert!(wait.abs_diff(Duration::from_secs(6)) < Duration::from_millis(1));
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = user_limiter(2, 6);
+        let now = Instant::now();
+        assert_eq!(limiter.acquire_at(USER, GROUP, now), Ok(()));
+        assert_eq!(limiter.acquire_at(USER, GROUP, now), Ok(()));
+        assert!(limiter.acquire_at(USER, GROUP, now).is_err());
+
+        // One token after 10 seconds
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.acquire_at(USER, GROUP, later), Ok(()));
+        assert!(limiter.acquire_at(USER, GROUP, later).is_err());
+
+        // Never more than the burst after a long quiet period
+        let much_later = later + Duration::from_secs(3600);
+        assert_eq!(limiter.acquire_at(USER, GROUP, much_later), Ok(()));
+        assert_eq!(limiter.acquire_at(USER, GROUP, much_later), Ok(()));
+        assert!(limiter.acquire_at(USER, GROUP, much_later).is_err());
+    }
+
+    #[test]
+    fn combines_the_user_and_group_limits() {
+        let limiter = RateLimiter::new(
+            Limit {
+                burst: 2,
+                per_minute: 60,
+            },
+            Limit {
+                burst: 3,
+                per_minute: 6,
+            },
+        );
+        let now = Instant::now();
+        assert_eq!(limiter.acquire_at(USER, GROUP, now), Ok(()));
+        assert_eq!(limiter.acquire_at(USER, GROUP, now), Ok(()));
+        // The customer ran out of tokens, the group still has one
+        let wait = limiter.acquire_at(USER, GROUP, now).unwrap_err();
+        assert!(wait.abs_diff(Duration::from_secs(1)) < Duration::from_millis(1));
+        // A refused message takes no token of the group, so another customer gets it
+        assert_eq!(limiter.acquire_at(OTHER_USER, GROUP, now), Ok(()));
+
+        // Now the group is the one to wait for, even if the customer has a token again
+        let later = now + Duration::from_secs(1);
+        let wait = limiter.acquire_at(USER, GROUP, later).unwrap_err();
+        assert!(wait.abs_diff(Duration::from_secs(9)) < Duration::from_millis(1));
+        // Other groups are not limited by this one
+        assert_eq!(limiter.acquire_at(USER, ChatId(-200), later), Ok(()));
+    }
+}
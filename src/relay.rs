@@ -0,0 +1,432 @@
+// relay.rs
+use std::iter;
+use teloxide::prelude::*;
+use teloxide::types::{
+    InputFile, InputMedia, InputMediaAnimation, InputMediaAudio, InputMediaDocument,
+    InputMediaPhoto, InputMediaVideo, MessageEntity, MessageId, ReplyParameters, ThreadId,
+};
+use teloxide::ApiError;
+
+/// Text with its formatting, signed with the name of `author` in bold on the first line if any
+fn signed(
+    text: &str,
+    entities: Option<&[MessageEntity]>,
+    author: Option<&str>,
+) -> (String, Vec<MessageEntity>) {
+    let entities = entities.unwrap_or_default().to_vec();
+    let Some(author) = author else {
+        return (text.to_string(), entities);
+    };
+
+    // The offsets of the entities are in UTF-16 code units
+    let header = format!("{}:", author);
+    let header_len = header.encode_utf16().count();
+    let bold = MessageEntity::bold(0, header_len);
+    if text.is_empty() {
+        return (header, vec![bold]);
+    }
+
+    let entities = iter::once(bold)
+        .chain(entities.into_iter().map(|mut entity| {
+            entity.offset += header_len + 1;
+            entity
+        }))
+        .collect();
+    (format!("{}\n{}", header, text), entities)
+}
+
+/// Formatted caption of `msg`, signed with the name of `author` if any
+fn signed_caption(msg: &Message, author: Option<&str>) -> Option<(String, Vec<MessageEntity>)> {
+    if msg.caption().is_none() && author.is_none() {
+        return None;
+    }
+    Some(signed(
+        msg.caption().unwrap_or_default(),
+        msg.caption_entities(),
+        author,
+    ))
+}
+
+/// Sends a copy of a media request, adding the formatted caption of the original message signed by `author`,
+/// and the destination topic and reply if any
+macro_rules! send_media {
+    ($request:expr, $msg:expr, $author:expr, $thread_id:expr, $reply_to:expr) => {{
+        let mut request = $request;
+        if let Some((caption, entities)) = signed_caption($msg, $author) {
+            request = request.caption(caption);
+            if !entities.is_empty() {
+                request = request.caption_entities(entities);
+            }
+        }
+        send_plain!(request, $thread_id, $reply_to)
+    }};
+}
+
+/// Sends a request without caption (stickers and video notes), adding the destination topic and reply if any
+macro_rules! send_plain {
+    ($request:expr, $thread_id:expr, $reply_to:expr) => {{
+        let mut request = $request;
+        if let Some(thread_id) = $thread_id {
+            request = request.message_thread_id(thread_id);
+        }
+        if let Some(reply_to) = $reply_to {
+            // The replied message may have been deleted in the meantime
+            request = request
+                .reply_parameters(ReplyParameters::new(reply_to).allow_sending_without_reply());
+        }
+        request.await?
+    }};
+}
+
+/// Longest text of a message accepted by Telegram, in UTF-16 code units
+pub const MAX_TEXT_LENGTH: usize = 4096;
+
+/// Texts one after the other on separate lines, keeping their formatting
+fn joined<'a>(
+    texts: impl IntoIterator<Item = (&'a str, Option<&'a [MessageEntity]>)>,
+) -> (String, Vec<MessageEntity>) {
+    let mut text = String::new();
+    let mut entities = Vec::new();
+    for (part, part_entities) in texts {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        // The offsets of the entities are in UTF-16 code units
+        let offset = text.encode_utf16().count();
+        entities.extend(part_entities.unwrap_or_default().iter().map(|entity| {
+            let mut entity = entity.clone();
+            entity.offset += offset;
+            entity
+        }));
+        text.push_str(part);
+    }
+    (text, entities)
+}
+
+/// Relays the texts of `messages` together as a single message to `chat_id`, one after the other on separate lines,
+/// keeping their formatting. With an `author`, the copy is signed with their name.
+pub async fn relay_texts(
+    bot: &Bot,
+    messages: &[Message],
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    author: Option<&str>,
+) -> Result<Message, teloxide::RequestError> {
+    let (text, entities) = joined(
+        messages
+            .iter()
+            .map(|msg| (msg.text().unwrap_or_default(), msg.entities())),
+    );
+    let (text, entities) = signed(&text, Some(&entities), author);
+    let mut request = bot.send_message(chat_id, text);
+    if !entities.is_empty() {
+        request = request.entities(entities);
+    }
+    Ok(send_plain!(request, thread_id, None::<MessageId>))
+}
+
+/// Relays the content of `msg` (text or any supported media, with its formatting) to `chat_id`, inside the forum topic `thread_id` if any,
+/// as a reply to `reply_to` if any. With an `author`, the copy is signed with their name.
+/// Returns the sent message, or `None` if the message has nothing that can be relayed.
+pub async fn relay_message(
+    bot: &Bot,
+    msg: &Message,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    reply_to: Option<MessageId>,
+    author: Option<&str>,
+) -> Result<Option<Message>, teloxide::RequestError> {
+    // Stickers and video notes have no caption, so the name of the author goes right before them
+    if let Some(author) = author.filter(|_| msg.sticker().is_some() || msg.video_note().is_some()) {
+        let (header, entities) = signed("", None, Some(author));
+        let mut request = bot.send_message(chat_id, header).entities(entities);
+        if let Some(thread_id) = thread_id {
+            request = request.message_thread_id(thread_id);
+        }
+        request.await?;
+    }
+
+    let sent = if let Some(text) = msg.text() {
+        let (text, entities) = signed(text, msg.entities(), author);
+        let mut request = bot.send_message(chat_id, text);
+        if !entities.is_empty() {
+            request = request.entities(entities);
+        }
+        send_plain!(request, thread_id, reply_to)
+    } else if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+        send_media!(
+            bot.send_photo(chat_id, InputFile::file_id(photo.file.id.clone())),
+            msg,
+            author,
+            thread_id,
+            reply_to
+        )
+    } else if let Some(document) = msg.document() {
+        send_media!(
+            bot.send_document(chat_id, InputFile::file_id(document.file.id.clone())),
+            msg,
+            author,
+            thread_id,
+            reply_to
+        )
+    } else if let Some(video) = msg.video() {
+        send_media!(
+            bot.send_video(chat_id, InputFile::file_id(video.file.id.clone())),
+            msg,
+            author,
+            thread_id,
+            reply_to
+        )
+    } else if let Some(voice) = msg.voice() {
+        send_media!(
+            bot.send_voice(chat_id, InputFile::file_id(voice.file.id.clone())),
+            msg,
+            author,
+            thread_id,
+            reply_to
+        )
+    } else if let Some(audio) = msg.audio() {
+        send_media!(
+            bot.send_audio(chat_id, InputFile::file_id(audio.file.id.clone())),
+            msg,
+            author,
+            thread_id,
+            reply_to
+        )
+    } else if let Some(animation) = msg.animation() {
+        send_media!(
+            bot.send_animation(chat_id, InputFile::file_id(animation.file.id.clone())),
+            msg,
+            author,
+            thread_id,
+            reply_to
+        )
+    } else if let Some(sticker) = msg.sticker() {
+        send_plain!(
+            bot.send_sticker(chat_id, InputFile::file_id(sticker.file.id.clone())),
+            thread_id,
+            reply_to
+        )
+    } else if let Some(video_note) = msg.video_note() {
+        send_plain!(
+            bot.send_video_note(chat_id, InputFile::file_id(video_note.file.id.clone())),
+            thread_id,
+            reply_to
+        )
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(sent))
+}
+
+/// Adds the formatted caption of `msg` signed by `author`, if any, to an input media
+macro_rules! with_caption {
+    ($media:expr, $msg:expr, $author:expr) => {{
+        let mut media = $media;
+        if let Some((caption, entities)) = signed_caption($msg, $author) {
+            media = media.caption(caption);
+            if !entities.is_empty() {
+                media = media.caption_entities(entities);
+            }
+        }
+        media
+    }};
+}
+
+/// Media of `msg` with its caption signed by `author`, to replace the one of its copy or to be sent in an album
+fn input_media(msg: &Message, author: Option<&str>) -> Option<InputMedia> {
+    let media = if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+        InputMedia::Photo(with_caption!(
+            InputMediaPhoto::new(InputFile::file_id(photo.file.id.clone())),
+            msg,
+            author
+        ))
+    } else if let Some(document) = msg.document() {
+        InputMedia::Document(with_caption!(
+            InputMediaDocument::new(InputFile::file_id(document.file.id.clone())),
+            msg,
+            author
+        ))
+    } else if let Some(video) = msg.video() {
+        InputMedia::Video(with_caption!(
+            InputMediaVideo::new(InputFile::file_id(video.file.id.clone())),
+            msg,
+            author
+        ))
+    } else if let Some(audio) = msg.audio() {
+        InputMedia::Audio(with_caption!(
+            InputMediaAudio::new(InputFile::file_id(audio.file.id.clone())),
+            msg,
+            author
+        ))
+    } else if let Some(animation) = msg.animation() {
+        InputMedia::Animation(with_caption!(
+            InputMediaAnimation::new(InputFile::file_id(animation.file.id.clone())),
+            msg,
+            author
+        ))
+    } else {
+        return None;
+    };
+
+    Some(media)
+}
+
+/// Applies the edit of `msg` to its copy `message_id` in `chat_id`: the text, or the media with its caption.
+/// The copies signed by `author` stay signed.
+/// Fails when the copy can't be edited, for example because it is too old.
+pub async fn relay_edit(
+    bot: &Bot,
+    msg: &Message,
+    chat_id: ChatId,
+    message_id: MessageId,
+    author: Option<&str>,
+) -> Result<(), teloxide::RequestError> {
+    let result = if let Some(text) = msg.text() {
+        let (text, entities) = signed(text, msg.entities(), author);
+        let mut request = bot.edit_message_text(chat_id, message_id, text);
+        if !entities.is_empty() {
+            request = request.entities(entities);
+        }
+        request.await.map(|_| ())
+    } else if let Some(media) = input_media(msg, author) {
+        bot.edit_message_media(chat_id, message_id, media)
+            .await
+            .map(|_| ())
+    } else {
+        // Voice messages can only change their caption
+        let mut request = bot.edit_message_caption(chat_id, message_id);
+        if let Some((caption, entities)) = signed_caption(msg, author) {
+            request = request.caption(caption);
+            if !entities.is_empty() {
+                request = request.caption_entities(entities);
+            }
+        }
+        request.await.map(|_| ())
+    };
+
+    match result {
+        // The edit didn't change what was relayed
+        Err(teloxide::RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+        result => result,
+    }
+}
+
+/// Relays the items of an album together as a single media group to `chat_id`, inside the forum topic `thread_id` if any,
+/// as a reply to `reply_to` if any. With an `author`, the first item is signed with their name.
+/// Returns each relayed item with its copy; items that can't be part of an album are skipped.
+pub async fn relay_media_group<'a>(
+    bot: &Bot,
+    msgs: &'a [Message],
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    reply_to: Option<MessageId>,
+    author: Option<&str>,
+) -> Result<Vec<(&'a Message, Message)>, teloxide::RequestError> {
+    let (items, media): (Vec<&Message>, Vec<InputMedia>) = msgs
+        .iter()
+        .enumerate()
+        .filter_map(|(index, msg)| {
+            let author = author.filter(|_| index == 0);
+            input_media(msg, author).map(|media| (msg, media))
+        })
+        .unzip();
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sent = send_plain!(bot.send_media_group(chat_id, media), thread_id, reply_to);
+
+    Ok(items.into_iter().zip(sent).collect())
+}
+
+/// Returns a short description of the message content for the console log
+pub fn describe_message(msg: &Message) -> String {
+    if let Some(text) = msg.text() {
+        return text.to_string();
+    }
+
+    let kind = if msg.photo().is_some() {
+        "photo"
+    } else if msg.document().is_some() {
+        "document"
+    } else if msg.video().is_some() {
+        "video"
+    } else if msg.voice().is_some() {
+        "voice"
+    } else if msg.audio().is_some() {
+        "audio"
+    } else if msg.animation().is_some() {
+        "animation"
+    } else if msg.sticker().is_some() {
+        "sticker"
+    } else if msg.video_note().is_some() {
+        "video note"
+    } else {
+        "unsupported"
+    };
+
+    match msg.caption() {
+        Some(caption) => format!("[{}] {}", kind, caption),
+        None => format!("[{}]", kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Part of `text` covered by `entity`
+    fn covered(text: &str, entity: &MessageEntity) -> String {
+        let units: Vec<u16> = text.encode_utf16().collect();
+        String::from_utf16(&units[entity.offset..entity.offset + entity.length]).unwrap()
+    }
+
+    #[test]
+    fn shifts_the_entities_after_the_signature() {
+        // Both the name and the text are outside of the BMP, so they take more UTF-16 units than characters
+        let text = "🔥 fire and 🌊 water";
+        let entities = [MessageEntity::bold(0, 2), MessageEntity::italic(15, 5)];
+        let (signed_text, signed_entities) = signed(text, Some(&entities), Some("Zoë 🦊"));
+
+        assert_eq!(signed_text, "Zoë 🦊:\n🔥 fire and 🌊 water");
+        let covered: Vec<_> = signed_entities
+            .iter()
+            .map(|entity| covered(&signed_text, entity))
+            .collect();
+        assert_eq!(covered, ["Zoë 🦊:", "🔥", "water"]);
+    }
+
+    #[test]
+    fn keeps_the_entities_unsigned() {
+        let entities = [MessageEntity::bold(0, 2)];
+        let (text, signed_entities) = signed("🔥 fire", Some(&entities), None);
+        assert_eq!(text, "🔥 fire");
+        assert_eq!(signed_entities, entities);
+
+        let (header, header_entities) = signed("", None, Some("Zoë 🦊"));
+        assert_eq!(header, "Zoë 🦊:");
+        assert_eq!(header_entities, [MessageEntity::bold(0, 7)]);
+    }
+
+    #[test]
+    fn shifts_the_entities_of_the_joined_texts() {
+        let first = [MessageEntity::bold(3, 2)];
+        let second = [MessageEntity::italic(0, 2), MessageEntity::code(3, 5)];
+        let (text, entities) = joined([
+            ("😀 hi", Some(&first[..])),
+            ("plain", None),
+            ("👋 there", Some(&second[..])),
+        ]);
+        assert_eq!(text, "😀 hi\nplain\n👋 there");
+
+        // Signing shifts the entities of every text, before and after the joins
+        let (text, entities) = signed(&text, Some(&entities), Some("Zoë 🦊"));
+        let covered: Vec<_> = entities
+            .iter()
+            .map(|entity| covered(&text, entity))
+            .collect();
+        assert_eq!(covered, ["Zoë 🦊:", "hi", "👋", "there"]);
+    }
+}
@@ -0,0 +1,66 @@
+// routing.rs
+use crate::category::{categories, Category};
+use crate::config::config;
+use crate::state::{support_group_id, Language};
+use serde::Deserialize;
+use std::fs;
+use std::sync::OnceLock;
+use teloxide::types::{Chat, ChatId};
+
+/// A rule of the routing table: the new tickets matching the category and the language go to the group.
+/// A missing category or language matches any.
+#[derive(Deserialize)]
+struct Route {
+    category: Option<String>,
+    language: Option<String>,
+    group: i64,
+}
+
+impl Route {
+    fn matches(&self, category: &str, language: Language) -> bool {
+        self.category.as_deref().is_none_or(|c| c == category)
+            && self
+                .language
+                .as_deref()
+                .is_none_or(|l| l == language.code())
+    }
+}
+
+/// Routing table, empty when no file is configured
+fn routes() -> &'static [Route] {
+    static ROUTES: OnceLock<Vec<Route>> = OnceLock::new();
+    ROUTES.get_or_init(|| {
+        let path = &config().routes_path;
+        if !path.exists() {
+            return Vec::new();
+        }
+        let json = fs::read_to_string(path).expect("Unable to read the routing table");
+        serde_json::from_str(&json).expect("Invalid routing table")
+    })
+}
+
+/// Support group receiving the new tickets of the category in the language: the first matching route,
+/// or else the group of the category, or else `SUPPORT_GROUP`
+pub fn route_ticket(category: &Category, language: Language) -> ChatId {
+    routes()
+        .iter()
+        .find(|route| route.matches(&category.id, language))
+        .map(|route| route.group)
+        .or(category.route)
+        .map(ChatId)
+        .unwrap_or_else(support_group_id)
+}
+
+/// Whether the chat is one of the support groups, where tickets have their topics
+pub fn is_support_group(chat_id: ChatId) -> bool {
+    chat_id == support_group_id()
+        || routes().iter().any(|route| ChatId(route.group) == chat_id)
+        || categories()
+            .iter()
+            .any(|category| category.route == Some(chat_id.0))
+}
+
+/// Whether customers can open tickets from the chat: the private chats, and the groups that aren't support groups
+pub fn is_customer_chat(chat: &Chat) -> bool {
+    chat.is_private() || ((chat.is_group() || chat.is_supergroup()) && !is_support_group(chat.id))
+}
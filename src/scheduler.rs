@@ -0,0 +1,136 @@
+// scheduler.rs
+use crate::config::config;
+use crate::i18n::tr;
+use crate::state::StateContainer;
+use crate::storage::{CloseReason, Ticket};
+use crate::ticket::close_ticket;
+use chrono::{Local, Utc};
+use colored::Colorize;
+use std::sync::Arc;
+use teloxide::prelude::*;
+
+/// Starts the background task warning about and then closing the inactive tickets
+pub fn spawn_inactivity_scheduler(bot: Bot, state: Arc<StateContainer>) {
+    if config().inactivity_warning.is_zero() {
+        println!("Automatic close of inactive tickets disabled");
+        return;
+    }
+
+    let period = config()
+        .inactivity_check_interval
+        .to_std()
+        .expect("INACTIVITY_CHECK_MINUTES must be positive");
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            check_inactive_tickets(&bot, &state).await;
+        }
+    });
+}
+
+/// Warns the users of the tickets inactive for too long, and closes the ones already warned after the grace period
+async fn check_inactive_tickets(bot: &Bot, state: &StateContainer) {
+    let now = Utc::now();
+    let tickets = state
+        .storage
+        .inactive_tickets(now - config().inactivity_warning)
+        .unwrap_or_else(|e| {
+            eprintln!("Error loading the inactive tickets: {}", e);
+            Vec::new()
+        });
+
+    for mut ticket in tickets {
+        match ticket.inactivity_warned_at {
+            None => warn_inactive_ticket(bot, state, &ticket).await,
+            Some(warned_at) if now - warned_at >= config().inactivity_grace => {
+                close_inactive_ticket(bot, state, &mut ticket).await
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Warns the user and the support group that the ticket is going to be closed.
+/// A failed notice is only logged, so it doesn't stop the other one.
+async fn warn_inactive_ticket(bot: &Bot, state: &StateContainer, ticket: &Ticket) {
+    // Mark the ticket first, so it is closed after the grace period even if the user can't be reached
+    if let Err(e) = state.storage.mark_inactivity_warned(ticket.id) {
+        eprintln!("Error updating ticket #{}: {}", ticket.id, e);
+        return;
+    }
+
+    let hours = config().inactivity_grace.num_hours();
+    if let Err(e) = bot
+        .send_message(
+            ticket.chat_id,
+            tr!(ticket.language, "inactivity-warning", hours = hours),
+        )
+        .await
+    {
+        eprintln!(
+            "Error warning the user of the inactive ticket #{}: {}",
+            ticket.id, e
+        );
+    }
+    if let Err(e) = bot
+        .send_message(
+            ticket.group_id,
+            tr!(
+                config().staff_language,
+                "staff-inactivity-warning",
+                hours = hours
+            ),
+        )
+        .message_thread_id(ticket.thread_id)
+        .await
+    {
+        eprintln!(
+            "Error warning the topic of the inactive ticket #{}: {}",
+            ticket.id, e
+        );
+    }
+
+    println!(
+        "{} {} warned {} about the inactive ticket",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[INACTIVE]".bold().yellow(),
+        ticket.opener_name.bold().blue()
+    );
+}
+
+/// Closes the ticket, then tells the support group and the user.
+/// A failed notice is only logged, so the ticket is closed even if its topic or the user can't be reached.
+async fn close_inactive_ticket(bot: &Bot, state: &StateContainer, ticket: &mut Ticket) {
+    close_ticket(bot, state, ticket, CloseReason::Inactivity).await;
+
+    if let Err(e) = bot
+        .send_message(
+            ticket.group_id,
+            tr!(config().staff_language, "staff-chat-ended-inactivity"),
+        )
+        .message_thread_id(ticket.thread_id)
+        .await
+    {
+        eprintln!(
+            "Error telling the topic of ticket #{} about the close: {}",
+            ticket.id, e
+        );
+    }
+    if let Err(e) = bot
+        .send_message(ticket.chat_id, tr!(ticket.language, "inactivity-closed"))
+        .await
+    {
+        eprintln!(
+            "Error telling the user of ticket #{} about the close: {}",
+            ticket.id, e
+        );
+    }
+
+    println!(
+        "{} {} the ticket of {} has been closed for inactivity",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        "[CLOSE - INACTIVE]".bold().red(),
+        ticket.opener_name.bold().blue()
+    );
+}
@@ -1,43 +1,73 @@
-use serde::{Deserialize, Serialize};
+use crate::config::config;
+use crate::i18n::{tr, LOCALES};
+use crate::ratelimit::RateLimiter;
+use crate::storage::{import_bindings_json, open_storage, Storage, Ticket};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
-use std::path::Path;
+use std::env;
 use std::sync::Arc;
 use std::sync::OnceLock;
-use std::{env, fs};
-use teloxide::types::{ChatId, MessageId};
+use teloxide::types::{ChatId, Message, MessageId, ThreadId, UserId};
 use tokio::sync::Mutex;
 
-// Aggiungi derive per serializzazione/deserializzazione
-#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub enum Language {
-    Italian,
-    English,
-}
+/// A language of the bot, one for each locale file. Stored as its code (`it`, `en`, ...).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Language(&'static str);
 
 impl Language {
-    pub fn to_flag(&self) -> &'static str {
-        match self {
-            Language::Italian => "🇮🇹",
-            Language::English => "🇬🇧",
-        }
+    /// Language of the messages sent when nothing is known about the user
+    pub const DEFAULT: Language = Language("en");
+
+    /// Flag shown on the language buttons and in the topic names
+    pub fn to_flag(&self) -> String {
+        tr!(*self, "language-flag")
+    }
+
+    /// Name of the language, in the language itself
+    pub fn name(self) -> String {
+        tr!(self, "language-name")
+    }
+
+    pub fn code(self) -> &'static str {
+        self.0
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        LOCALES
+            .iter()
+            .find(|(locale, _)| *locale == code)
+            .map(|(locale, _)| Language(locale))
+    }
+
+    /// Language matching the IETF tag of a Telegram profile (`it`, `pt-br`...), also by its primary subtag
+    pub fn detect(language_code: &str) -> Option<Self> {
+        let code = language_code.to_lowercase().replace('_', "-");
+        Self::from_code(&code).or_else(|| Self::from_code(code.split('-').next()?))
+    }
+
+    /// All the languages with a locale file
+    pub fn all() -> impl Iterator<Item = Language> {
+        LOCALES.iter().map(|(code, _)| Language(code))
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum TicketType {
-    Bug,
-    HowTo,
-    Other,
+impl Serialize for Language {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
 }
 
-impl TicketType {
-    pub fn to_string(&self) -> String {
-        match self {
-            TicketType::Bug => "Bug",
-            TicketType::HowTo => "How to ...",
-            TicketType::Other => "Other",
-        }
-        .to_string()
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        // Before the locale files the languages were saved by name
+        let code = match code.as_str() {
+            "Italian" => "it",
+            "English" => "en",
+            code => code,
+        };
+        Language::from_code(code)
+            .ok_or_else(|| de::Error::custom(format!("unknown language {}", code)))
     }
 }
 
@@ -54,58 +84,94 @@ pub fn support_group_id() -> ChatId {
     })
 }
 
+/// Ticket creation in progress for a single user
+#[derive(Clone, Default)]
+pub struct PendingTicket {
+    pub language: Option<Language>,
+    /// Id of the chosen category, set while the intake form is being filled
+    pub category: Option<String>,
+    /// Answers to the intake questions so far, `None` for the skipped ones
+    pub answers: Vec<Option<String>>,
+    /// Message asking the current intake question
+    pub question_message: Option<MessageId>,
+    /// Message asking the team of the user, while waiting for the answer
+    pub team_question: Option<MessageId>,
+    /// Whether the team was already asked in this ticket creation
+    pub team_asked: bool,
+}
+
+/// Queues of the messages over the rate limit, by chat and customer
+pub type FloodQueues = HashMap<(ChatId, UserId), Vec<Message>>;
+
 /// Container for the application state
 #[derive(Clone)]
 pub struct StateContainer {
-    /// Maps private ChatId to topic MessageId
-    pub bindings: Arc<Mutex<HashMap<ChatId, MessageId>>>,
-    /// Stores the ChatId, selected language and ticket type of the user who requested the last topic
-    pub pending_chat: Arc<Mutex<Option<(ChatId, Language, Option<TicketType>)>>>,
+    /// Persistence of the support tickets
+    pub storage: Arc<dyn Storage>,
+    /// Maps private ChatId to the ticket that user is creating
+    pub pending: Arc<Mutex<HashMap<ChatId, PendingTicket>>>,
+    /// Items of the albums being received, by media group id, relayed together once complete
+    pub albums: Arc<Mutex<HashMap<String, Vec<Message>>>>,
+    /// Rate limits of the messages relayed to the support
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Messages of the customers over the rate limit, relayed as the limits allow
+    pub floods: Arc<Mutex<FloodQueues>>,
 }
 
+/// Binding saved in `bindings.json` by the previous versions of the bot
 #[derive(Serialize, Deserialize)]
 pub struct SavedBinding {
     pub chat_id: i64,
+    /// Id of the topic creation message, which is also the topic thread id
     pub topic_msg_id: i32,
 }
 
 impl StateContainer {
-    // Nuova funzione per salvare i bindings su file
-    pub async fn save_bindings(&self) -> Result<(), std::io::Error> {
-        let bindings = self.bindings.lock().await;
-        let saved_bindings: Vec<SavedBinding> = bindings
-            .iter()
-            .map(|(&chat_id, &topic_msg_id)| SavedBinding {
-                chat_id: chat_id.0,
-                topic_msg_id: topic_msg_id.0,
-            })
-            .collect();
-
-        let json = serde_json::to_string_pretty(&saved_bindings)?;
-        fs::write("/data/bindings.json", json)
-    }
+    pub fn new() -> Self {
+        let storage = open_storage(&config().storage).expect("Unable to open the tickets storage");
 
-    // Nuova funzione per caricare i bindings da file
-    pub fn load_bindings() -> HashMap<ChatId, MessageId> {
-        let path = Path::new("/data/bindings.json");
-        if !path.exists() {
-            println!("No bindings file found, starting with an empty state.");
-            return HashMap::new();
+        // Import the bindings saved by the previous versions of the bot. The file is renamed once imported, so the
+        // tickets must outlive the restarts.
+        if config().storage.is_persistent() {
+            match import_bindings_json(storage.as_ref(), &config().legacy_bindings_path) {
+                Ok(0) => {}
+                Ok(imported) => println!("Imported {} tickets from bindings.json", imported),
+                Err(e) => eprintln!("Error importing bindings.json: {}", e),
+            }
         }
 
-        let json = fs::read_to_string(path).unwrap_or_default();
-        let saved_bindings: Vec<SavedBinding> = serde_json::from_str(&json).unwrap_or_default();
+        Self {
+            storage,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            albums: Arc::new(Mutex::new(HashMap::new())),
+            rate_limiter: Arc::new(RateLimiter::new(
+                config().user_rate_limit,
+                config().group_rate_limit,
+            )),
+            floods: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
 
-        saved_bindings
-            .into_iter()
-            .map(|b| (ChatId(b.chat_id), MessageId(b.topic_msg_id)))
-            .collect()
+    /// Returns the active ticket of the private chat, logging database errors
+    pub fn ticket_by_chat(&self, chat_id: ChatId) -> Option<Ticket> {
+        self.storage
+            .active_ticket_by_chat(chat_id)
+            .unwrap_or_else(|e| {
+                eprintln!("Error loading the ticket of chat {}: {}", chat_id, e);
+                None
+            })
     }
 
-    pub fn new() -> Self {
-        Self {
-            bindings: Arc::new(Mutex::new(Self::load_bindings())),
-            pending_chat: Arc::new(Mutex::new(None)),
-        }
+    /// Returns the active ticket bound to the topic of the support group, logging database errors
+    pub fn ticket_by_thread(&self, group_id: ChatId, thread_id: ThreadId) -> Option<Ticket> {
+        self.storage
+            .active_ticket_by_thread(group_id, thread_id)
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "Error loading the ticket of topic {}: {}",
+                    thread_id.0 .0, e
+                );
+                None
+            })
     }
 }
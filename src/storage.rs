@@ -0,0 +1,1100 @@
+// storage.rs
+mod json;
+mod memory;
+mod sqlite;
+
+use crate::config::StorageBackend;
+use crate::state::{support_group_id, Language, SavedBinding};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::types::{ChatId, MessageId, ThreadId, UserId};
+
+pub use json::JsonStorage;
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+
+/// Lifecycle of a ticket: Open → Waiting on customer → Resolved → Closed
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketStatus {
+    /// Waiting for an answer of RustBusters
+    Open,
+    /// RustBusters answered, waiting for the customer
+    WaitingOnCustomer,
+    /// RustBusters marked the ticket as resolved, the customer can still write to reopen it
+    Resolved,
+    /// The ticket is over and only kept in the history
+    Closed,
+}
+
+impl TicketStatus {
+    pub fn code(self) -> &'static str {
+        match self {
+            TicketStatus::Open => "open",
+            TicketStatus::WaitingOnCustomer => "waiting_on_customer",
+            TicketStatus::Resolved => "resolved",
+            TicketStatus::Closed => "closed",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "open" => Some(TicketStatus::Open),
+            "waiting_on_customer" => Some(TicketStatus::WaitingOnCustomer),
+            "resolved" => Some(TicketStatus::Resolved),
+            "closed" => Some(TicketStatus::Closed),
+            _ => None,
+        }
+    }
+
+    /// Prefix of the topic name showing the status
+    pub fn topic_prefix(self) -> &'static str {
+        match self {
+            TicketStatus::Open => "🟢",
+            TicketStatus::WaitingOnCustomer => "⏳",
+            TicketStatus::Resolved => "✅",
+            TicketStatus::Closed => "🔒",
+        }
+    }
+
+    /// Whether the topic of a ticket in this status is closed in the support group
+    pub fn closes_topic(self) -> bool {
+        matches!(self, TicketStatus::Resolved | TicketStatus::Closed)
+    }
+}
+
+/// Why a ticket was closed
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// Closed by the customer
+    User,
+    /// Closed by a member of the support group
+    Staff,
+    /// Closed automatically after a long time without messages
+    Inactivity,
+    /// Closed because the customer was banned
+    Banned,
+}
+
+impl CloseReason {
+    pub fn code(self) -> &'static str {
+        match self {
+            CloseReason::User => "user",
+            CloseReason::Staff => "staff",
+            CloseReason::Inactivity => "inactivity",
+            CloseReason::Banned => "banned",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "user" => Some(CloseReason::User),
+            "staff" => Some(CloseReason::Staff),
+            "inactivity" => Some(CloseReason::Inactivity),
+            "banned" => Some(CloseReason::Banned),
+            _ => None,
+        }
+    }
+}
+
+/// A support ticket, bound to a topic of the support group
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ticket {
+    pub id: i64,
+    /// Private chat of the user who opened the ticket, or the customer group it was opened in
+    pub chat_id: ChatId,
+    /// Support group of the ticket topic, tickets saved before the routing live in the default one
+    #[serde(default = "support_group_id")]
+    pub group_id: ChatId,
+    /// Thread of the ticket topic in the support group
+    pub thread_id: ThreadId,
+    pub language: Language,
+    /// Id of the category of the ticket
+    #[serde(alias = "ticket_type")]
+    pub category: String,
+    pub opened_by: UserId,
+    pub opener_name: String,
+    pub status: TicketStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Last message relayed in either direction
+    #[serde(default = "Utc::now")]
+    pub last_activity_at: DateTime<Utc>,
+    /// When the user was warned that the ticket is going to be closed for inactivity
+    #[serde(default)]
+    pub inactivity_warned_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub close_reason: Option<CloseReason>,
+    /// Member of the support group handling the ticket
+    #[serde(default)]
+    pub assigned_to: Option<UserId>,
+    #[serde(default)]
+    pub assignee_name: Option<String>,
+    /// Team of the customer when the ticket was opened, whose members share the ticket
+    #[serde(default)]
+    pub team_id: Option<i64>,
+    /// Name of the team, or title of the customer group the ticket was opened in, shown in the topic name
+    #[serde(default)]
+    pub team_name: Option<String>,
+}
+
+impl Ticket {
+    /// Builds the just created open ticket with the given id
+    fn new_open(id: i64, new: NewTicket, now: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            chat_id: new.chat_id,
+            group_id: new.group_id,
+            thread_id: new.thread_id,
+            language: new.language,
+            category: new.category,
+            opened_by: new.opened_by,
+            opener_name: new.opener_name,
+            status: TicketStatus::Open,
+            created_at: now,
+            updated_at: now,
+            closed_at: None,
+            last_activity_at: now,
+            inactivity_warned_at: None,
+            close_reason: None,
+            assigned_to: None,
+            assignee_name: None,
+            team_id: new.team_id,
+            team_name: new.team_name,
+        }
+    }
+}
+
+/// A member of the support group, remembered when they write in the group so tickets can be assigned to them
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaffMember {
+    pub user_id: UserId,
+    pub username: Option<String>,
+    /// Name shown in the topic of the assigned tickets
+    pub name: String,
+}
+
+/// What is remembered about a customer across tickets
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UserProfile {
+    pub user_id: UserId,
+    /// Name of the user when they last contacted the bot
+    #[serde(default)]
+    pub display_name: String,
+    /// Language chosen by the user, preferred to the one of the Telegram profile
+    pub language: Option<Language>,
+    /// Team or organization the user belongs to, as they declared it
+    #[serde(default)]
+    pub team: Option<String>,
+    /// Team the user joined with its invite code
+    #[serde(default)]
+    pub team_id: Option<i64>,
+    /// Whether the user is in the allowlist of the customers who can open tickets
+    #[serde(default)]
+    pub allowed: bool,
+    #[serde(default = "Utc::now")]
+    pub first_contact_at: DateTime<Utc>,
+    /// Number of tickets opened by the user, counted from the tickets
+    #[serde(skip)]
+    pub ticket_count: usize,
+}
+
+/// A team of customers, whose members share their tickets. Users join it with its invite code.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Team {
+    pub id: i64,
+    pub name: String,
+    pub invite_code: String,
+    pub created_at: DateTime<Utc>,
+    /// Whether the members of the team can open tickets when the allowlist is enabled
+    #[serde(default)]
+    pub allowed: bool,
+}
+
+/// A license code sold to a new customer, adding them to the allowlist when they redeem it. Each code works once.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct License {
+    pub code: String,
+    pub created_at: DateTime<Utc>,
+    pub redeemed_by: Option<UserId>,
+    pub redeemed_at: Option<DateTime<Utc>>,
+}
+
+/// A customer banned or muted by the support group, whose messages are ignored by the bot
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BlockedUser {
+    pub user_id: UserId,
+    /// End of the mute, `None` for a ban
+    pub until: Option<DateTime<Utc>>,
+    /// Member of the support group who banned or muted the customer
+    pub blocked_by: UserId,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BlockedUser {
+    /// Whether the ban or the mute is still in force
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_none_or(|until| until > now)
+    }
+}
+
+/// Internal note of the support group on a ticket, never relayed to the customer
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub id: i64,
+    pub ticket_id: i64,
+    pub author_id: UserId,
+    pub author_name: String,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data needed to record a new internal note
+pub struct NewNote {
+    pub ticket_id: i64,
+    pub author_id: UserId,
+    pub author_name: String,
+    pub text: String,
+}
+
+/// A message relayed between the private chats and the topic, with one of the copies sent by the bot
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RelayedMessage {
+    pub ticket_id: i64,
+    pub source_chat_id: ChatId,
+    pub source_message_id: MessageId,
+    /// Author of the original message
+    #[serde(default)]
+    pub source_user_id: Option<UserId>,
+    pub copy_chat_id: ChatId,
+    pub copy_message_id: MessageId,
+    /// The copy holds the texts of several messages relayed together, so it can't follow the edits of one of them
+    #[serde(default)]
+    pub coalesced: bool,
+}
+
+/// Data needed to record a newly created ticket
+pub struct NewTicket {
+    pub chat_id: ChatId,
+    pub group_id: ChatId,
+    pub thread_id: ThreadId,
+    pub language: Language,
+    pub category: String,
+    pub opened_by: UserId,
+    pub opener_name: String,
+    pub team_id: Option<i64>,
+    pub team_name: Option<String>,
+}
+
+/// Error of a storage backend
+#[derive(Debug)]
+pub enum StorageError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Sqlite(rusqlite::Error),
+    /// The change conflicts with the stored data
+    Conflict(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(e) => write!(f, "I/O error: {}", e),
+            StorageError::Json(e) => write!(f, "invalid JSON: {}", e),
+            StorageError::Sqlite(e) => write!(f, "database error: {}", e),
+            StorageError::Conflict(e) => write!(f, "conflict: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Json(e)
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// Persistence layer of the bot
+pub trait Storage: Send + Sync {
+    /// Records a new open ticket
+    fn create_ticket(&self, new: NewTicket) -> StorageResult<Ticket>;
+
+    /// Records the given tickets at once, skipping the chats that already have an active ticket.
+    /// Returns the number of tickets actually recorded.
+    fn import_tickets(&self, tickets: Vec<NewTicket>) -> StorageResult<usize>;
+
+    /// Returns the ticket of the private chat that is not closed yet
+    fn active_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>>;
+
+    /// Returns the ticket bound to the topic of the support group that is not closed yet
+    fn active_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>>;
+
+    /// Returns the most recently closed ticket of the private chat
+    fn last_closed_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>>;
+
+    /// Returns the most recent ticket bound to the topic of the support group, even if closed
+    fn last_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>>;
+
+    /// Returns the tickets not closed yet without messages since `before`
+    fn inactive_tickets(&self, before: DateTime<Utc>) -> StorageResult<Vec<Ticket>>;
+
+    /// Records a message relayed for the ticket, clearing the inactivity warning
+    fn touch_ticket(&self, id: i64) -> StorageResult<()>;
+
+    /// Records that the user was warned about the inactivity of the ticket
+    fn mark_inactivity_warned(&self, id: i64) -> StorageResult<()>;
+
+    /// Moves the ticket to `status`, with the reason when it is closed. Closed tickets are kept in the history, but
+    /// their relayed messages are forgotten.
+    fn set_ticket_status(
+        &self,
+        id: i64,
+        status: TicketStatus,
+        close_reason: Option<CloseReason>,
+    ) -> StorageResult<()>;
+
+    /// Assigns the ticket to a member of the support group
+    fn assign_ticket(&self, id: i64, assignee: &StaffMember) -> StorageResult<()>;
+
+    /// Returns the tickets not closed yet assigned to the member of the support group
+    fn tickets_assigned_to(&self, user_id: UserId) -> StorageResult<Vec<Ticket>>;
+
+    /// Records (or updates) a member of the support group
+    fn save_staff_member(&self, member: &StaffMember) -> StorageResult<()>;
+
+    /// Returns the member of the support group with the username, ignoring the case
+    fn staff_member_by_username(&self, username: &str) -> StorageResult<Option<StaffMember>>;
+
+    /// Records an internal note of the support group on a ticket
+    fn add_note(&self, note: NewNote) -> StorageResult<()>;
+
+    /// Records the copy of a relayed message, replacing the previous copy of the same message in the same chat
+    fn save_relayed_message(&self, relayed: &RelayedMessage) -> StorageResult<()>;
+
+    /// Returns the copies of the message sent in `chat_id`, one for each chat it was relayed to
+    fn relayed_copies(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Vec<RelayedMessage>>;
+
+    /// Returns the relayed message whose copy is the message of `chat_id`
+    fn relayed_message_by_copy(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Option<RelayedMessage>>;
+
+    /// Changes the language of the ticket
+    fn set_ticket_language(&self, id: i64, language: Language) -> StorageResult<()>;
+
+    /// Returns the profile of the customer, if they already contacted the bot
+    fn user_profile(&self, user_id: UserId) -> StorageResult<Option<UserProfile>>;
+
+    /// Records a contact of the customer, creating their profile the first time and updating their name
+    fn save_user(&self, user_id: UserId, display_name: &str) -> StorageResult<()>;
+
+    /// Records the language preferred by the customer, creating their profile if needed
+    fn set_user_language(&self, user_id: UserId, language: Language) -> StorageResult<()>;
+
+    /// Records the team of the customer, creating their profile if needed
+    fn set_user_team(&self, user_id: UserId, team: &str) -> StorageResult<()>;
+
+    /// Records a new team. Fails if a team with the same name, ignoring the case, already exists.
+    fn create_team(&self, name: &str, invite_code: &str) -> StorageResult<Team>;
+
+    fn team(&self, id: i64) -> StorageResult<Option<Team>>;
+
+    /// Returns the team with the name, ignoring the case
+    fn team_by_name(&self, name: &str) -> StorageResult<Option<Team>>;
+
+    fn team_by_invite_code(&self, invite_code: &str) -> StorageResult<Option<Team>>;
+
+    /// Adds the customer to the team, creating their profile if needed
+    fn join_team(&self, user_id: UserId, team_id: i64) -> StorageResult<()>;
+
+    /// Returns the customers who joined the team
+    fn team_members(&self, team_id: i64) -> StorageResult<Vec<UserId>>;
+
+    /// Returns the tickets of the team not closed yet
+    fn active_tickets_by_team(&self, team_id: i64) -> StorageResult<Vec<Ticket>>;
+
+    /// Adds the customer to the allowlist or removes them, creating their profile if needed
+    fn set_user_allowed(&self, user_id: UserId, allowed: bool) -> StorageResult<()>;
+
+    /// Adds the team to the allowlist or removes it
+    fn set_team_allowed(&self, team_id: i64, allowed: bool) -> StorageResult<()>;
+
+    /// Returns the profiles of the customers in the allowlist
+    fn allowed_users(&self) -> StorageResult<Vec<UserProfile>>;
+
+    /// Returns the teams in the allowlist
+    fn allowed_teams(&self) -> StorageResult<Vec<Team>>;
+
+    /// Records a new license code. Fails if the code already exists.
+    fn create_license(&self, code: &str) -> StorageResult<License>;
+
+    /// Redeems the license code for the customer, adding them to the allowlist.
+    /// Returns `None` if the code doesn't exist or was already redeemed.
+    fn redeem_license(&self, code: &str, user_id: UserId) -> StorageResult<Option<License>>;
+
+    /// Bans the customer, or mutes them until `until`, replacing their previous ban or mute
+    fn block_user(
+        &self,
+        user_id: UserId,
+        until: Option<DateTime<Utc>>,
+        blocked_by: UserId,
+    ) -> StorageResult<()>;
+
+    /// Lifts the ban or the mute of the customer. Returns whether they were still blocked.
+    fn unblock_user(&self, user_id: UserId) -> StorageResult<bool>;
+
+    /// Returns the ban or the mute of the customer still in force
+    fn active_block(&self, user_id: UserId) -> StorageResult<Option<BlockedUser>>;
+}
+
+/// Opens the storage backend selected in the configuration
+pub fn open_storage(backend: &StorageBackend) -> StorageResult<Arc<dyn Storage>> {
+    Ok(match backend {
+        StorageBackend::Memory => Arc::new(MemoryStorage::new()),
+        StorageBackend::Json(path) => Arc::new(JsonStorage::open(path)?),
+        StorageBackend::Sqlite(path) => Arc::new(SqliteStorage::open(path)?),
+    })
+}
+
+/// Imports the bindings saved by the previous versions of the bot as open tickets.
+/// The file is renamed afterwards, so the import happens only once.
+pub fn import_bindings_json(storage: &dyn Storage, path: &Path) -> StorageResult<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let json = fs::read_to_string(path)?;
+    let saved_bindings: Vec<SavedBinding> = serde_json::from_str(&json)?;
+
+    // Language and category were not saved: use the defaults. In private chats the chat id is the user id.
+    let tickets = saved_bindings
+        .into_iter()
+        .map(|binding| NewTicket {
+            chat_id: ChatId(binding.chat_id),
+            group_id: support_group_id(),
+            thread_id: ThreadId(MessageId(binding.topic_msg_id)),
+            language: Language::DEFAULT,
+            category: "other".to_string(),
+            opened_by: UserId(binding.chat_id as u64),
+            opener_name: "imported".to_string(),
+            team_id: None,
+            team_name: None,
+        })
+        .collect();
+    let imported = storage.import_tickets(tickets)?;
+
+    let mut done = path.as_os_str().to_owned();
+    done.push(".imported");
+    fs::rename(path, done)?;
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const CUSTOMER: UserId = UserId(1);
+    const TEAMMATE: UserId = UserId(2);
+    const STAFF: UserId = UserId(3);
+    const GROUP: ChatId = ChatId(-100);
+
+    /// Every backend is tested the same way, the SQLite one being migrated from an empty database.
+    /// The file of the JSON one is removed at the end of the test.
+    fn backends() -> Vec<(&'static str, Box<dyn Storage>, Option<TempFile>)> {
+        // Opening the SQLite storage fills in the support group of the old tickets
+        std::env::set_var("SUPPORT_GROUP", GROUP.0.to_string());
+        let file = TempFile::new("json");
+        vec![
+            ("memory", Box::new(MemoryStorage::new()), None),
+            (
+                "json",
+                Box::new(JsonStorage::open(&file.0).unwrap()),
+                Some(file),
+            ),
+            (
+                "sqlite",
+                Box::new(SqliteStorage::open(":memory:").unwrap()),
+                None,
+            ),
+        ]
+    }
+
+    /// New file in the temporary directory, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(extension: &str) -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "support-bot-test-{}-{}.{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+                extension
+            ));
+            let _ = fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn new_ticket(chat_id: ChatId, thread_id: i32) -> NewTicket {
+        NewTicket {
+            chat_id,
+            group_id: GROUP,
+            thread_id: ThreadId(MessageId(thread_id)),
+            language: Language::DEFAULT,
+            category: "bug".to_string(),
+            opened_by: CUSTOMER,
+            opener_name: "Customer".to_string(),
+            team_id: None,
+            team_name: None,
+        }
+    }
+
+    #[test]
+    fn creates_and_loads_tickets() {
+        for (backend, storage, _file) in backends() {
+            let created = storage.create_ticket(new_ticket(ChatId(1), 10)).unwrap();
+            let other = storage.create_ticket(new_ticket(ChatId(2), 20)).unwrap();
+            assert_ne!(created.id, other.id, "{}", backend);
+            assert_eq!(created.status, TicketStatus::Open, "{}", backend);
+
+            let by_chat = storage.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+            assert_eq!(by_chat.id, created.id, "{}", backend);
+            assert_eq!(by_chat.group_id, GROUP, "{}", backend);
+            assert_eq!(by_chat.category, "bug", "{}", backend);
+            assert_eq!(by_chat.opened_by, CUSTOMER, "{}", backend);
+            assert_eq!(by_chat.opener_name, "Customer", "{}", backend);
+            assert_eq!(by_chat.language, Language::DEFAULT, "{}", backend);
+
+            let thread_id = ThreadId(MessageId(20));
+            let by_thread = storage.active_ticket_by_thread(GROUP, thread_id).unwrap();
+            assert_eq!(by_thread.map(|t| t.id), Some(other.id), "{}", backend);
+            let elsewhere = storage.active_ticket_by_thread(ChatId(-200), thread_id);
+            assert!(elsewhere.unwrap().is_none(), "{}", backend);
+            assert!(storage.active_ticket_by_chat(ChatId(3)).unwrap().is_none());
+
+            // A chat has a single active ticket
+            assert!(
+                matches!(
+                    storage.create_ticket(new_ticket(ChatId(1), 30)),
+                    Err(StorageError::Conflict(_))
+                ),
+                "{}",
+                backend
+            );
+        }
+    }
+
+    #[test]
+    fn changes_the_status_of_tickets() {
+        for (backend, storage, _file) in backends() {
+            let ticket = storage.create_ticket(new_ticket(ChatId(1), 10)).unwrap();
+            let thread_id = ticket.thread_id;
+
+            storage
+                .set_ticket_status(ticket.id, TicketStatus::WaitingOnCustomer, None)
+                .unwrap();
+            let waiting = storage.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+            assert_eq!(
+                waiting.status,
+                TicketStatus::WaitingOnCustomer,
+                "{}",
+                backend
+            );
+
+            storage
+                .set_ticket_status(ticket.id, TicketStatus::Closed, Some(CloseReason::Staff))
+                .unwrap();
+            assert!(storage.active_ticket_by_chat(ChatId(1)).unwrap().is_none());
+            let closed = storage
+                .last_closed_ticket_by_chat(ChatId(1))
+                .unwrap()
+                .unwrap();
+            assert_eq!(closed.id, ticket.id, "{}", backend);
+            assert_eq!(closed.status, TicketStatus::Closed, "{}", backend);
+            assert_eq!(closed.close_reason, Some(CloseReason::Staff), "{}", backend);
+            assert!(closed.closed_at.is_some(), "{}", backend);
+
+            // The topic still leads to the closed ticket
+            assert!(storage
+                .active_ticket_by_thread(GROUP, thread_id)
+                .unwrap()
+                .is_none());
+            let last = storage.last_ticket_by_thread(GROUP, thread_id).unwrap();
+            assert_eq!(last.map(|t| t.id), Some(ticket.id), "{}", backend);
+
+            // A new ticket can be opened in the same chat
+            let reopened = storage.create_ticket(new_ticket(ChatId(1), 30)).unwrap();
+            let active = storage.active_ticket_by_chat(ChatId(1)).unwrap();
+            assert_eq!(active.map(|t| t.id), Some(reopened.id), "{}", backend);
+        }
+    }
+
+    #[test]
+    fn maps_relayed_messages_to_their_copies() {
+        for (backend, storage, _file) in backends() {
+            let ticket = storage.create_ticket(new_ticket(ChatId(1), 10)).unwrap();
+            for (copy_chat_id, copy_message_id) in [(GROUP, 100), (ChatId(2), 200)] {
+                storage
+                    .save_relayed_message(&RelayedMessage {
+                        ticket_id: ticket.id,
+                        source_chat_id: ChatId(1),
+                        source_message_id: MessageId(5),
+                        source_user_id: Some(CUSTOMER),
+                        copy_chat_id,
+                        copy_message_id: MessageId(copy_message_id),
+                        coalesced: false,
+                    })
+                    .unwrap();
+            }
+
+            let copies = storage.relayed_copies(ChatId(1), MessageId(5)).unwrap();
+            let mut copies: Vec<_> = copies
+                .iter()
+                .map(|r| (r.copy_chat_id, r.copy_message_id))
+                .collect();
+            copies.sort_by_key(|(chat_id, _)| chat_id.0);
+            assert_eq!(
+                copies,
+                [(GROUP, MessageId(100)), (ChatId(2), MessageId(200))],
+                "{}",
+                backend
+            );
+            assert!(storage
+                .relayed_copies(ChatId(1), MessageId(6))
+                .unwrap()
+                .is_empty());
+
+            let source = storage
+                .relayed_message_by_copy(GROUP, MessageId(100))
+                .unwrap()
+                .unwrap();
+            assert_eq!(source.ticket_id, ticket.id, "{}", backend);
+            assert_eq!(source.source_chat_id, ChatId(1), "{}", backend);
+            assert_eq!(source.source_message_id, MessageId(5), "{}", backend);
+            assert_eq!(source.source_user_id, Some(CUSTOMER), "{}", backend);
+            assert!(!source.coalesced, "{}", backend);
+
+            // The messages of the closed tickets are kept while the tickets can be reopened
+            storage
+                .set_ticket_status(ticket.id, TicketStatus::Closed, Some(CloseReason::User))
+                .unwrap();
+            assert_eq!(
+                storage
+                    .relayed_copies(ChatId(1), MessageId(5))
+                    .unwrap()
+                    .len(),
+                2,
+                "{}",
+                backend
+            );
+            assert!(storage
+                .relayed_message_by_copy(GROUP, MessageId(100))
+                .unwrap()
+                .is_some());
+        }
+    }
+
+    #[test]
+    fn creates_and_joins_teams() {
+        for (backend, storage, _file) in backends() {
+            let team = storage.create_team("Acme", "JOIN-ACME").unwrap();
+            assert!(
+                matches!(
+                    storage.create_team("Acme", "OTHER-CODE"),
+                    Err(StorageError::Conflict(_))
+                ),
+                "{}",
+                backend
+            );
+            assert!(
+                matches!(
+                    storage.create_team("Globex", "JOIN-ACME"),
+                    Err(StorageError::Conflict(_))
+                ),
+                "{}",
+                backend
+            );
+            assert!(storage.team_by_name("Globex").unwrap().is_none());
+
+            let by_name = storage.team_by_name("Acme").unwrap().unwrap();
+            assert_eq!(by_name.id, team.id, "{}", backend);
+            let by_code = storage.team_by_invite_code("JOIN-ACME").unwrap().unwrap();
+            assert_eq!(by_code.name, "Acme", "{}", backend);
+            assert!(storage.team(team.id).unwrap().is_some(), "{}", backend);
+
+            storage.join_team(CUSTOMER, team.id).unwrap();
+            storage.join_team(TEAMMATE, team.id).unwrap();
+            let mut members = storage.team_members(team.id).unwrap();
+            members.sort_by_key(|user_id| user_id.0);
+            assert_eq!(members, [CUSTOMER, TEAMMATE], "{}", backend);
+            let profile = storage.user_profile(TEAMMATE).unwrap().unwrap();
+            assert_eq!(profile.team_id, Some(team.id), "{}", backend);
+
+            let ticket = storage
+                .create_ticket(NewTicket {
+                    team_id: Some(team.id),
+                    team_name: Some(team.name.clone()),
+                    ..new_ticket(ChatId(1), 10)
+                })
+                .unwrap();
+            let tickets = storage.active_tickets_by_team(team.id).unwrap();
+            let ids: Vec<i64> = tickets.iter().map(|t| t.id).collect();
+            assert_eq!(ids, [ticket.id], "{}", backend);
+        }
+    }
+
+    #[test]
+    fn blocks_and_unblocks_customers() {
+        for (backend, storage, _file) in backends() {
+            storage.block_user(CUSTOMER, None, STAFF).unwrap();
+            let block = storage.active_block(CUSTOMER).unwrap().unwrap();
+            assert_eq!(block.user_id, CUSTOMER, "{}", backend);
+            assert_eq!(block.blocked_by, STAFF, "{}", backend);
+            assert!(block.until.is_none(), "{}", backend);
+            assert!(storage.active_block(TEAMMATE).unwrap().is_none());
+
+            assert!(storage.unblock_user(CUSTOMER).unwrap(), "{}", backend);
+            assert!(storage.active_block(CUSTOMER).unwrap().is_none());
+            assert!(!storage.unblock_user(CUSTOMER).unwrap(), "{}", backend);
+
+            // A mute ends by itself, and lifting it afterwards changes nothing
+            let now = Utc::now();
+            storage
+                .block_user(TEAMMATE, Some(now + TimeDelta::hours(1)), STAFF)
+                .unwrap();
+            assert!(storage.active_block(TEAMMATE).unwrap().is_some());
+            storage
+                .block_user(TEAMMATE, Some(now - TimeDelta::hours(1)), STAFF)
+                .unwrap();
+            assert!(storage.active_block(TEAMMATE).unwrap().is_none());
+            assert!(!storage.unblock_user(TEAMMATE).unwrap(), "{}", backend);
+        }
+    }
+
+    #[test]
+    fn reloads_the_json_storage_from_its_file() {
+        let file = TempFile::new("json");
+        let path = &file.0;
+        let storage = JsonStorage::open(path).unwrap();
+        let ticket = storage.create_ticket(new_ticket(ChatId(1), 10)).unwrap();
+        storage.create_team("Acme", "JOIN-ACME").unwrap();
+        storage.block_user(CUSTOMER, None, STAFF).unwrap();
+        storage
+            .save_relayed_message(&RelayedMessage {
+                ticket_id: ticket.id,
+                source_chat_id: ChatId(1),
+                source_message_id: MessageId(5),
+                source_user_id: Some(CUSTOMER),
+                copy_chat_id: GROUP,
+                copy_message_id: MessageId(100),
+                coalesced: false,
+            })
+            .unwrap();
+        // The file is written next to the old one, then renamed over it
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        assert!(path.exists());
+        assert!(!Path::new(&tmp_path).exists());
+
+        let reloaded = JsonStorage::open(path).unwrap();
+        let loaded = reloaded.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+        assert_eq!(loaded.id, ticket.id);
+        assert_eq!(loaded.thread_id, ticket.thread_id);
+        assert!(reloaded.team_by_name("Acme").unwrap().is_some());
+        assert!(reloaded.active_block(CUSTOMER).unwrap().is_some());
+        assert_eq!(
+            reloaded
+                .relayed_copies(ChatId(1), MessageId(5))
+                .unwrap()
+                .len(),
+            1
+        );
+        // The ids go on from the saved ones
+        let next = reloaded.create_ticket(new_ticket(ChatId(2), 20)).unwrap();
+        assert!(next.id > ticket.id);
+    }
+
+    /// Mapping of the message 5 of `chat_id` in the ticket to its copy 100 in the support group
+    fn relayed_message(ticket_id: i64, chat_id: ChatId) -> RelayedMessage {
+        RelayedMessage {
+            ticket_id,
+            source_chat_id: chat_id,
+            source_message_id: MessageId(5),
+            source_user_id: Some(CUSTOMER),
+            copy_chat_id: GROUP,
+            copy_message_id: MessageId(100 + chat_id.0 as i32),
+            coalesced: false,
+        }
+    }
+
+    #[test]
+    fn prunes_the_json_messages_of_expired_tickets_on_open() {
+        let file = TempFile::new("json");
+        let path = &file.0;
+        let storage = JsonStorage::open(path).unwrap();
+        for chat_id in [ChatId(1), ChatId(2)] {
+            let ticket = storage.create_ticket(new_ticket(chat_id, 10)).unwrap();
+            storage
+                .save_relayed_message(&relayed_message(ticket.id, chat_id))
+                .unwrap();
+        }
+        drop(storage);
+
+        // A file with a ticket closed long ago and one closed right now, both with their messages still there
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        for (index, closed_at) in [Utc::now() - TimeDelta::days(30), Utc::now()]
+            .into_iter()
+            .enumerate()
+        {
+            json["tickets"][index]["status"] = "closed".into();
+            json["tickets"][index]["closed_at"] = serde_json::to_value(closed_at).unwrap();
+        }
+        fs::write(path, json.to_string()).unwrap();
+
+        let reloaded = JsonStorage::open(path).unwrap();
+        assert!(reloaded
+            .relayed_copies(ChatId(1), MessageId(5))
+            .unwrap()
+            .is_empty());
+        // The ticket closed right now can still be reopened
+        assert_eq!(
+            reloaded
+                .relayed_copies(ChatId(2), MessageId(5))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn prunes_the_sqlite_messages_of_expired_tickets_on_close() {
+        std::env::set_var("SUPPORT_GROUP", GROUP.0.to_string());
+        let file = TempFile::new("db");
+        let storage = SqliteStorage::open(&file.0).unwrap();
+        let mut tickets = Vec::new();
+        for chat_id in [ChatId(1), ChatId(2)] {
+            let ticket = storage.create_ticket(new_ticket(chat_id, 10)).unwrap();
+            storage
+                .save_relayed_message(&relayed_message(ticket.id, chat_id))
+                .unwrap();
+            tickets.push(ticket);
+        }
+        storage
+            .set_ticket_status(tickets[0].id, TicketStatus::Closed, Some(CloseReason::User))
+            .unwrap();
+        // The first ticket was closed long ago
+        rusqlite::Connection::open(&file.0)
+            .unwrap()
+            .execute(
+                "UPDATE tickets SET closed_at = ?2 WHERE id = ?1",
+                rusqlite::params![tickets[0].id, Utc::now() - TimeDelta::days(30)],
+            )
+            .unwrap();
+
+        // Closing the second one forgets the messages of the first, but keeps its own
+        storage
+            .set_ticket_status(tickets[1].id, TicketStatus::Closed, Some(CloseReason::User))
+            .unwrap();
+        assert!(storage
+            .relayed_copies(ChatId(1), MessageId(5))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            storage
+                .relayed_copies(ChatId(2), MessageId(5))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn saves_the_json_activity_lazily() {
+        let file = TempFile::new("json");
+        let path = &file.0;
+        let storage = JsonStorage::open(path).unwrap();
+        let ticket = storage.create_ticket(new_ticket(ChatId(1), 10)).unwrap();
+
+        // Right after a save, the new activity stays in memory
+        storage.touch_ticket(ticket.id).unwrap();
+        let touched = storage.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+        assert!(touched.last_activity_at > ticket.last_activity_at);
+        let saved = JsonStorage::open(path).unwrap();
+        let saved = saved.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+        assert_eq!(saved.last_activity_at, ticket.last_activity_at);
+
+        // Clearing an inactivity warning is saved right away
+        storage.mark_inactivity_warned(ticket.id).unwrap();
+        storage.touch_ticket(ticket.id).unwrap();
+        let saved = JsonStorage::open(path).unwrap();
+        let saved = saved.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+        assert!(saved.inactivity_warned_at.is_none());
+        assert!(saved.last_activity_at > ticket.last_activity_at);
+    }
+
+    #[test]
+    fn keeps_the_json_data_when_the_save_fails() {
+        let file = TempFile::new("json");
+        let path = &file.0;
+        let storage = JsonStorage::open(path).unwrap();
+        storage.create_license("LICENSE").unwrap();
+
+        // A directory in the way of the temporary file makes the saves fail
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        fs::create_dir(&tmp_path).unwrap();
+        assert!(storage.create_ticket(new_ticket(ChatId(1), 10)).is_err());
+        assert!(storage.active_ticket_by_chat(ChatId(1)).unwrap().is_none());
+        assert!(storage.redeem_license("LICENSE", CUSTOMER).is_err());
+
+        // Nothing was kept, so everything works once the saves do
+        fs::remove_dir(&tmp_path).unwrap();
+        assert!(storage.create_ticket(new_ticket(ChatId(1), 10)).is_ok());
+        let license = storage.redeem_license("LICENSE", CUSTOMER).unwrap();
+        assert_eq!(license.and_then(|l| l.redeemed_by), Some(CUSTOMER));
+    }
+
+    #[test]
+    fn imports_the_legacy_bindings_once() {
+        for (backend, storage, _file) in backends() {
+            let existing = storage.create_ticket(new_ticket(ChatId(1), 10)).unwrap();
+            let bindings = TempFile::new("json");
+            fs::write(
+                &bindings.0,
+                r#"[
+                    { "chat_id": 1, "topic_msg_id": 11 },
+                    { "chat_id": 2, "topic_msg_id": 20 },
+                    { "chat_id": 3, "topic_msg_id": 30 }
+                ]"#,
+            )
+            .unwrap();
+
+            // The chat with an active ticket keeps it
+            let imported = import_bindings_json(storage.as_ref(), &bindings.0).unwrap();
+            assert_eq!(imported, 2, "{}", backend);
+            let kept = storage.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+            assert_eq!(kept.id, existing.id, "{}", backend);
+
+            let ticket = storage.active_ticket_by_chat(ChatId(2)).unwrap().unwrap();
+            assert_eq!(ticket.group_id, GROUP, "{}", backend);
+            assert_eq!(ticket.thread_id, ThreadId(MessageId(20)), "{}", backend);
+            assert_eq!(ticket.opened_by, UserId(2), "{}", backend);
+            assert_eq!(ticket.status, TicketStatus::Open, "{}", backend);
+
+            // The file is renamed, so the next startup imports nothing
+            let mut done = bindings.0.as_os_str().to_owned();
+            done.push(".imported");
+            assert!(!bindings.0.exists(), "{}", backend);
+            assert!(Path::new(&done).exists(), "{}", backend);
+            assert_eq!(
+                import_bindings_json(storage.as_ref(), &bindings.0).unwrap(),
+                0,
+                "{}",
+                backend
+            );
+            let _ = fs::remove_file(&done);
+        }
+    }
+
+    #[test]
+    fn migrates_a_database_of_the_first_version() {
+        std::env::set_var("SUPPORT_GROUP", GROUP.0.to_string());
+        let file = TempFile::new("db");
+        let conn = rusqlite::Connection::open(&file.0).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tickets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                thread_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                ticket_type TEXT NOT NULL,
+                opened_by INTEGER NOT NULL,
+                opener_name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                closed_at TEXT
+            );
+            CREATE INDEX tickets_thread ON tickets (thread_id);
+            CREATE UNIQUE INDEX tickets_one_active_per_chat ON tickets (chat_id) WHERE status != 'closed';
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+        let created_at = Utc::now() - TimeDelta::days(1);
+        conn.execute(
+            "INSERT INTO tickets (chat_id, thread_id, language, ticket_type, opened_by, opener_name, status, created_at, updated_at)
+             VALUES (1, 10, 'en', 'bug', 1, 'Customer', 'open', ?1, ?1)",
+            rusqlite::params![created_at],
+        )
+        .unwrap();
+        drop(conn);
+
+        let storage = SqliteStorage::open(&file.0).unwrap();
+        let ticket = storage.active_ticket_by_chat(ChatId(1)).unwrap().unwrap();
+        assert_eq!(ticket.category, "bug");
+        assert_eq!(ticket.group_id, GROUP);
+        assert_eq!(ticket.thread_id, ThreadId(MessageId(10)));
+        assert_eq!(ticket.last_activity_at, created_at);
+        assert!(ticket.team_id.is_none());
+        // The migrated database works like a new one
+        storage
+            .join_team(
+                CUSTOMER,
+                storage.create_team("Acme", "JOIN-ACME").unwrap().id,
+            )
+            .unwrap();
+        storage.block_user(CUSTOMER, None, STAFF).unwrap();
+        assert!(storage.active_block(CUSTOMER).unwrap().is_some());
+        drop(storage);
+        // Opening it again applies nothing
+        let storage = SqliteStorage::open(&file.0).unwrap();
+        assert!(storage.active_ticket_by_chat(ChatId(1)).unwrap().is_some());
+    }
+}
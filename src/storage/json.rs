@@ -0,0 +1,358 @@
+// storage/json.rs
+use super::memory::MemoryData;
+use super::{
+    BlockedUser, CloseReason, License, NewNote, NewTicket, RelayedMessage, StaffMember, Storage,
+    StorageResult, Team, Ticket, TicketStatus, UserProfile,
+};
+use crate::state::Language;
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use teloxide::types::{ChatId, MessageId, ThreadId, UserId};
+
+/// Minimum time between two saves for the changes that are fine to lose, like the last activity of the tickets
+const LAZY_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Storage backend on a JSON file, rewritten on every change
+pub struct JsonStorage {
+    path: PathBuf,
+    data: Mutex<MemoryData>,
+    saved_at: Mutex<Instant>,
+}
+
+impl JsonStorage {
+    /// Loads the storage from `path`, starting empty if the file doesn't exist yet
+    pub fn open(path: impl AsRef<Path>) -> StorageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut data = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            MemoryData::default()
+        };
+        // The file may still hold the messages of the tickets whose reopen window has expired since the last close
+        data.prune_relayed_messages();
+
+        Ok(Self {
+            path,
+            data: Mutex::new(data),
+            saved_at: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Applies `change` to the data and saves it. The change is kept only once saved, so a failed save leaves the
+    /// data as it was. The file is written next to the old one and then renamed over it, so it is never left half
+    /// written.
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut MemoryData) -> StorageResult<T>,
+    ) -> StorageResult<T> {
+        let mut data = self.data.lock().unwrap();
+        let mut changed = data.clone();
+        let result = change(&mut changed)?;
+        self.save(&changed)?;
+        *data = changed;
+        Ok(result)
+    }
+
+    /// Applies `change` to the data, saving it only if `change` returns true or the last save is older than
+    /// `LAZY_SAVE_INTERVAL`. Otherwise the change is saved along with the next one.
+    fn update_lazily(&self, change: impl FnOnce(&mut MemoryData) -> bool) -> StorageResult<()> {
+        let mut data = self.data.lock().unwrap();
+        let urgent = change(&mut data);
+        if urgent || self.saved_at.lock().unwrap().elapsed() >= LAZY_SAVE_INTERVAL {
+            self.save(&data)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, data: &MemoryData) -> StorageResult<()> {
+        let mut tmp_path = self.path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(data)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        *self.saved_at.lock().unwrap() = Instant::now();
+        Ok(())
+    }
+}
+
+impl Storage for JsonStorage {
+    fn create_ticket(&self, new: NewTicket) -> StorageResult<Ticket> {
+        self.update(|data| data.create_ticket(new))
+    }
+
+    fn import_tickets(&self, tickets: Vec<NewTicket>) -> StorageResult<usize> {
+        self.update(|data| Ok(data.import_tickets(tickets)))
+    }
+
+    fn active_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .active_ticket_by_chat(chat_id)
+            .cloned())
+    }
+
+    fn active_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .active_ticket_by_thread(group_id, thread_id)
+            .cloned())
+    }
+
+    fn last_closed_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .last_closed_ticket_by_chat(chat_id)
+            .cloned())
+    }
+
+    fn last_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .last_ticket_by_thread(group_id, thread_id)
+            .cloned())
+    }
+
+    fn inactive_tickets(&self, before: DateTime<Utc>) -> StorageResult<Vec<Ticket>> {
+        Ok(self.data.lock().unwrap().inactive_tickets(before))
+    }
+
+    fn touch_ticket(&self, id: i64) -> StorageResult<()> {
+        // Relayed on every message, the last activity is saved at most once in a while. A cleared inactivity warning
+        // is saved right away instead, or the ticket could be closed after a restart.
+        self.update_lazily(|data| data.touch_ticket(id))
+    }
+
+    fn mark_inactivity_warned(&self, id: i64) -> StorageResult<()> {
+        self.update(|data| {
+            data.mark_inactivity_warned(id);
+            Ok(())
+        })
+    }
+
+    fn set_ticket_status(
+        &self,
+        id: i64,
+        status: TicketStatus,
+        close_reason: Option<CloseReason>,
+    ) -> StorageResult<()> {
+        self.update(|data| {
+            data.set_ticket_status(id, status, close_reason);
+            Ok(())
+        })
+    }
+
+    fn assign_ticket(&self, id: i64, assignee: &StaffMember) -> StorageResult<()> {
+        self.update(|data| {
+            data.assign_ticket(id, assignee);
+            Ok(())
+        })
+    }
+
+    fn tickets_assigned_to(&self, user_id: UserId) -> StorageResult<Vec<Ticket>> {
+        Ok(self.data.lock().unwrap().tickets_assigned_to(user_id))
+    }
+
+    fn save_staff_member(&self, member: &StaffMember) -> StorageResult<()> {
+        // Called for every message of the support group: don't rewrite the file when nothing changed
+        if self.data.lock().unwrap().is_staff_member_saved(member) {
+            return Ok(());
+        }
+        self.update(|data| {
+            data.save_staff_member(member);
+            Ok(())
+        })
+    }
+
+    fn staff_member_by_username(&self, username: &str) -> StorageResult<Option<StaffMember>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .staff_member_by_username(username)
+            .cloned())
+    }
+
+    fn add_note(&self, note: NewNote) -> StorageResult<()> {
+        self.update(|data| {
+            data.add_note(note);
+            Ok(())
+        })
+    }
+
+    fn save_relayed_message(&self, relayed: &RelayedMessage) -> StorageResult<()> {
+        self.update(|data| {
+            data.save_relayed_message(relayed);
+            Ok(())
+        })
+    }
+
+    fn relayed_copies(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Vec<RelayedMessage>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .relayed_copies(chat_id, message_id))
+    }
+
+    fn relayed_message_by_copy(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Option<RelayedMessage>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .relayed_message_by_copy(chat_id, message_id)
+            .cloned())
+    }
+
+    fn set_ticket_language(&self, id: i64, language: Language) -> StorageResult<()> {
+        self.update(|data| {
+            data.set_ticket_language(id, language);
+            Ok(())
+        })
+    }
+
+    fn user_profile(&self, user_id: UserId) -> StorageResult<Option<UserProfile>> {
+        Ok(self.data.lock().unwrap().user_profile(user_id))
+    }
+
+    fn save_user(&self, user_id: UserId, display_name: &str) -> StorageResult<()> {
+        // Called for every contact of the user: don't rewrite the file when nothing changed
+        if self
+            .data
+            .lock()
+            .unwrap()
+            .is_user_saved(user_id, display_name)
+        {
+            return Ok(());
+        }
+        self.update(|data| {
+            data.save_user(user_id, display_name);
+            Ok(())
+        })
+    }
+
+    fn set_user_language(&self, user_id: UserId, language: Language) -> StorageResult<()> {
+        self.update(|data| {
+            data.set_user_language(user_id, language);
+            Ok(())
+        })
+    }
+
+    fn set_user_team(&self, user_id: UserId, team: &str) -> StorageResult<()> {
+        self.update(|data| {
+            data.set_user_team(user_id, team);
+            Ok(())
+        })
+    }
+
+    fn create_team(&self, name: &str, invite_code: &str) -> StorageResult<Team> {
+        self.update(|data| data.create_team(name, invite_code))
+    }
+
+    fn team(&self, id: i64) -> StorageResult<Option<Team>> {
+        Ok(self.data.lock().unwrap().team(id).cloned())
+    }
+
+    fn team_by_name(&self, name: &str) -> StorageResult<Option<Team>> {
+        Ok(self.data.lock().unwrap().team_by_name(name).cloned())
+    }
+
+    fn team_by_invite_code(&self, invite_code: &str) -> StorageResult<Option<Team>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .team_by_invite_code(invite_code)
+            .cloned())
+    }
+
+    fn join_team(&self, user_id: UserId, team_id: i64) -> StorageResult<()> {
+        self.update(|data| {
+            data.join_team(user_id, team_id);
+            Ok(())
+        })
+    }
+
+    fn team_members(&self, team_id: i64) -> StorageResult<Vec<UserId>> {
+        Ok(self.data.lock().unwrap().team_members(team_id))
+    }
+
+    fn active_tickets_by_team(&self, team_id: i64) -> StorageResult<Vec<Ticket>> {
+        Ok(self.data.lock().unwrap().active_tickets_by_team(team_id))
+    }
+
+    fn set_user_allowed(&self, user_id: UserId, allowed: bool) -> StorageResult<()> {
+        self.update(|data| {
+            data.set_user_allowed(user_id, allowed);
+            Ok(())
+        })
+    }
+
+    fn set_team_allowed(&self, team_id: i64, allowed: bool) -> StorageResult<()> {
+        self.update(|data| {
+            data.set_team_allowed(team_id, allowed);
+            Ok(())
+        })
+    }
+
+    fn allowed_users(&self) -> StorageResult<Vec<UserProfile>> {
+        Ok(self.data.lock().unwrap().allowed_users())
+    }
+
+    fn allowed_teams(&self) -> StorageResult<Vec<Team>> {
+        Ok(self.data.lock().unwrap().allowed_teams())
+    }
+
+    fn create_license(&self, code: &str) -> StorageResult<License> {
+        self.update(|data| data.create_license(code))
+    }
+
+    fn redeem_license(&self, code: &str, user_id: UserId) -> StorageResult<Option<License>> {
+        self.update(|data| Ok(data.redeem_license(code, user_id)))
+    }
+
+    fn block_user(
+        &self,
+        user_id: UserId,
+        until: Option<DateTime<Utc>>,
+        blocked_by: UserId,
+    ) -> StorageResult<()> {
+        self.update(|data| {
+            data.block_user(user_id, until, blocked_by);
+            Ok(())
+        })
+    }
+
+    fn unblock_user(&self, user_id: UserId) -> StorageResult<bool> {
+        self.update(|data| Ok(data.unblock_user(user_id)))
+    }
+
+    fn active_block(&self, user_id: UserId) -> StorageResult<Option<BlockedUser>> {
+        Ok(self.data.lock().unwrap().active_block(user_id).cloned())
+    }
+}
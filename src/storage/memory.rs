@@ -0,0 +1,692 @@
+// storage/memory.rs
+use super::{
+    BlockedUser, CloseReason, License, NewNote, NewTicket, Note, RelayedMessage, StaffMember,
+    Storage, StorageError, StorageResult, Team, Ticket, TicketStatus, UserProfile,
+};
+use crate::config::config;
+use crate::state::Language;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use teloxide::types::{ChatId, MessageId, ThreadId, UserId};
+
+/// Whole content of the storage, kept in memory and shared with the JSON backend
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(super) struct MemoryData {
+    next_ticket_id: i64,
+    tickets: Vec<Ticket>,
+    #[serde(default)]
+    staff: Vec<StaffMember>,
+    #[serde(default)]
+    notes: Vec<Note>,
+    #[serde(default)]
+    relayed_messages: Vec<RelayedMessage>,
+    #[serde(default)]
+    users: Vec<UserProfile>,
+    #[serde(default)]
+    teams: Vec<Team>,
+    #[serde(default)]
+    licenses: Vec<License>,
+    #[serde(default)]
+    blocked_users: Vec<BlockedUser>,
+}
+
+impl MemoryData {
+    pub(super) fn active_ticket_by_chat(&self, chat_id: ChatId) -> Option<&Ticket> {
+        self.tickets
+            .iter()
+            .find(|t| t.chat_id == chat_id && t.status != TicketStatus::Closed)
+    }
+
+    pub(super) fn last_closed_ticket_by_chat(&self, chat_id: ChatId) -> Option<&Ticket> {
+        self.tickets
+            .iter()
+            .filter(|t| t.chat_id == chat_id && t.status == TicketStatus::Closed)
+            .max_by_key(|t| t.closed_at)
+    }
+
+    pub(super) fn last_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> Option<&Ticket> {
+        self.tickets
+            .iter()
+            .filter(|t| t.group_id == group_id && t.thread_id == thread_id)
+            .max_by_key(|t| t.created_at)
+    }
+
+    fn ticket_mut(&mut self, id: i64) -> Option<&mut Ticket> {
+        self.tickets.iter_mut().find(|t| t.id == id)
+    }
+
+    /// Records a new open ticket, unless the chat already has an active one
+    fn insert_ticket(&mut self, new: NewTicket) -> Option<Ticket> {
+        if self.active_ticket_by_chat(new.chat_id).is_some() {
+            return None;
+        }
+
+        self.next_ticket_id += 1;
+        let ticket = Ticket::new_open(self.next_ticket_id, new, Utc::now());
+        self.tickets.push(ticket.clone());
+        Some(ticket)
+    }
+
+    pub(super) fn create_ticket(&mut self, new: NewTicket) -> StorageResult<Ticket> {
+        let chat_id = new.chat_id;
+        self.insert_ticket(new).ok_or_else(|| {
+            StorageError::Conflict(format!("chat {} already has an active ticket", chat_id))
+        })
+    }
+
+    pub(super) fn import_tickets(&mut self, tickets: Vec<NewTicket>) -> usize {
+        tickets
+            .into_iter()
+            .filter_map(|new| self.insert_ticket(new))
+            .count()
+    }
+
+    pub(super) fn active_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> Option<&Ticket> {
+        self.tickets.iter().find(|t| {
+            t.group_id == group_id && t.thread_id == thread_id && t.status != TicketStatus::Closed
+        })
+    }
+
+    pub(super) fn inactive_tickets(&self, before: DateTime<Utc>) -> Vec<Ticket> {
+        self.tickets
+            .iter()
+            .filter(|t| t.status != TicketStatus::Closed && t.last_activity_at < before)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns whether an inactivity warning was cleared
+    pub(super) fn touch_ticket(&mut self, id: i64) -> bool {
+        let Some(ticket) = self.ticket_mut(id) else {
+            return false;
+        };
+        let now = Utc::now();
+        ticket.updated_at = now;
+        ticket.last_activity_at = now;
+        ticket.inactivity_warned_at.take().is_some()
+    }
+
+    pub(super) fn mark_inactivity_warned(&mut self, id: i64) {
+        if let Some(ticket) = self.ticket_mut(id) {
+            ticket.inactivity_warned_at = Some(Utc::now());
+        }
+    }
+
+    pub(super) fn set_ticket_status(
+        &mut self,
+        id: i64,
+        status: TicketStatus,
+        close_reason: Option<CloseReason>,
+    ) {
+        if let Some(ticket) = self.ticket_mut(id) {
+            let now = Utc::now();
+            ticket.status = status;
+            ticket.updated_at = now;
+            ticket.closed_at = (status == TicketStatus::Closed).then_some(now);
+            ticket.close_reason = close_reason;
+        }
+        if status == TicketStatus::Closed {
+            self.prune_relayed_messages();
+        }
+    }
+
+    /// Forgets the relayed messages of the tickets closed for longer than the reopen window, which can't be edited or
+    /// replied to anymore. The messages of the tickets that can still be reopened are kept with them.
+    pub(super) fn prune_relayed_messages(&mut self) {
+        let expired_before = Utc::now() - config().reopen_window;
+        let tickets = &self.tickets;
+        self.relayed_messages.retain(|r| {
+            tickets.iter().any(|t| {
+                t.id == r.ticket_id
+                    && t.closed_at
+                        .is_none_or(|closed_at| closed_at >= expired_before)
+            })
+        });
+    }
+
+    pub(super) fn assign_ticket(&mut self, id: i64, assignee: &StaffMember) {
+        if let Some(ticket) = self.ticket_mut(id) {
+            ticket.assigned_to = Some(assignee.user_id);
+            ticket.assignee_name = Some(assignee.name.clone());
+            ticket.updated_at = Utc::now();
+        }
+    }
+
+    pub(super) fn tickets_assigned_to(&self, user_id: UserId) -> Vec<Ticket> {
+        self.tickets
+            .iter()
+            .filter(|t| t.status != TicketStatus::Closed && t.assigned_to == Some(user_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the member is already saved exactly as given
+    pub(super) fn is_staff_member_saved(&self, member: &StaffMember) -> bool {
+        self.staff.contains(member)
+    }
+
+    pub(super) fn save_staff_member(&mut self, member: &StaffMember) {
+        match self.staff.iter_mut().find(|m| m.user_id == member.user_id) {
+            Some(saved) => *saved = member.clone(),
+            None => self.staff.push(member.clone()),
+        }
+    }
+
+    pub(super) fn staff_member_by_username(&self, username: &str) -> Option<&StaffMember> {
+        self.staff.iter().find(|m| {
+            m.username
+                .as_deref()
+                .is_some_and(|u| u.eq_ignore_ascii_case(username))
+        })
+    }
+
+    pub(super) fn add_note(&mut self, note: NewNote) {
+        self.notes.push(Note {
+            id: self.notes.len() as i64 + 1,
+            ticket_id: note.ticket_id,
+            author_id: note.author_id,
+            author_name: note.author_name,
+            text: note.text,
+            created_at: Utc::now(),
+        });
+    }
+
+    pub(super) fn save_relayed_message(&mut self, relayed: &RelayedMessage) {
+        self.relayed_messages.retain(|r| {
+            (r.source_chat_id, r.source_message_id, r.copy_chat_id)
+                != (
+                    relayed.source_chat_id,
+                    relayed.source_message_id,
+                    relayed.copy_chat_id,
+                )
+        });
+        self.relayed_messages.push(relayed.clone());
+    }
+
+    pub(super) fn set_ticket_language(&mut self, id: i64, language: Language) {
+        if let Some(ticket) = self.ticket_mut(id) {
+            ticket.language = language;
+            ticket.updated_at = Utc::now();
+        }
+    }
+
+    pub(super) fn user_profile(&self, user_id: UserId) -> Option<UserProfile> {
+        let mut profile = self.users.iter().find(|u| u.user_id == user_id)?.clone();
+        profile.ticket_count = self
+            .tickets
+            .iter()
+            .filter(|t| t.opened_by == user_id)
+            .count();
+        Some(profile)
+    }
+
+    fn user_profile_mut(&mut self, user_id: UserId) -> &mut UserProfile {
+        let index = match self.users.iter().position(|u| u.user_id == user_id) {
+            Some(index) => index,
+            None => {
+                self.users.push(UserProfile {
+                    user_id,
+                    display_name: String::new(),
+                    language: None,
+                    team: None,
+                    team_id: None,
+                    allowed: false,
+                    first_contact_at: Utc::now(),
+                    ticket_count: 0,
+                });
+                self.users.len() - 1
+            }
+        };
+        &mut self.users[index]
+    }
+
+    /// Whether the user is already saved with the name
+    pub(super) fn is_user_saved(&self, user_id: UserId, display_name: &str) -> bool {
+        self.users
+            .iter()
+            .any(|u| u.user_id == user_id && u.display_name == display_name)
+    }
+
+    pub(super) fn save_user(&mut self, user_id: UserId, display_name: &str) {
+        self.user_profile_mut(user_id).display_name = display_name.to_string();
+    }
+
+    pub(super) fn set_user_language(&mut self, user_id: UserId, language: Language) {
+        self.user_profile_mut(user_id).language = Some(language);
+    }
+
+    pub(super) fn set_user_team(&mut self, user_id: UserId, team: &str) {
+        self.user_profile_mut(user_id).team = Some(team.to_string());
+    }
+
+    pub(super) fn create_team(&mut self, name: &str, invite_code: &str) -> StorageResult<Team> {
+        if self.team_by_name(name).is_some() {
+            return Err(StorageError::Conflict(format!(
+                "team {} already exists",
+                name
+            )));
+        }
+        if self.team_by_invite_code(invite_code).is_some() {
+            return Err(StorageError::Conflict(format!(
+                "invite code {} already used",
+                invite_code
+            )));
+        }
+
+        let team = Team {
+            id: self.teams.len() as i64 + 1,
+            name: name.to_string(),
+            invite_code: invite_code.to_string(),
+            created_at: Utc::now(),
+            allowed: false,
+        };
+        self.teams.push(team.clone());
+        Ok(team)
+    }
+
+    pub(super) fn team(&self, id: i64) -> Option<&Team> {
+        self.teams.iter().find(|t| t.id == id)
+    }
+
+    pub(super) fn team_by_name(&self, name: &str) -> Option<&Team> {
+        self.teams
+            .iter()
+            .find(|t| t.name.to_lowercase() == name.to_lowercase())
+    }
+
+    pub(super) fn team_by_invite_code(&self, invite_code: &str) -> Option<&Team> {
+        self.teams.iter().find(|t| t.invite_code == invite_code)
+    }
+
+    pub(super) fn join_team(&mut self, user_id: UserId, team_id: i64) {
+        self.user_profile_mut(user_id).team_id = Some(team_id);
+    }
+
+    pub(super) fn team_members(&self, team_id: i64) -> Vec<UserId> {
+        self.users
+            .iter()
+            .filter(|u| u.team_id == Some(team_id))
+            .map(|u| u.user_id)
+            .collect()
+    }
+
+    pub(super) fn active_tickets_by_team(&self, team_id: i64) -> Vec<Ticket> {
+        self.tickets
+            .iter()
+            .filter(|t| t.status != TicketStatus::Closed && t.team_id == Some(team_id))
+            .cloned()
+            .collect()
+    }
+
+    pub(super) fn set_user_allowed(&mut self, user_id: UserId, allowed: bool) {
+        self.user_profile_mut(user_id).allowed = allowed;
+    }
+
+    pub(super) fn set_team_allowed(&mut self, team_id: i64, allowed: bool) {
+        if let Some(team) = self.teams.iter_mut().find(|t| t.id == team_id) {
+            team.allowed = allowed;
+        }
+    }
+
+    pub(super) fn allowed_users(&self) -> Vec<UserProfile> {
+        self.users
+            .iter()
+            .filter(|u| u.allowed)
+            .filter_map(|u| self.user_profile(u.user_id))
+            .collect()
+    }
+
+    pub(super) fn allowed_teams(&self) -> Vec<Team> {
+        self.teams.iter().filter(|t| t.allowed).cloned().collect()
+    }
+
+    pub(super) fn create_license(&mut self, code: &str) -> StorageResult<License> {
+        if self.licenses.iter().any(|l| l.code == code) {
+            return Err(StorageError::Conflict(format!(
+                "license {} already exists",
+                code
+            )));
+        }
+
+        let license = License {
+            code: code.to_string(),
+            created_at: Utc::now(),
+            redeemed_by: None,
+            redeemed_at: None,
+        };
+        self.licenses.push(license.clone());
+        Ok(license)
+    }
+
+    pub(super) fn redeem_license(&mut self, code: &str, user_id: UserId) -> Option<License> {
+        let license = self
+            .licenses
+            .iter_mut()
+            .find(|l| l.code == code && l.redeemed_by.is_none())?;
+        license.redeemed_by = Some(user_id);
+        license.redeemed_at = Some(Utc::now());
+        let license = license.clone();
+
+        self.set_user_allowed(user_id, true);
+        Some(license)
+    }
+
+    pub(super) fn block_user(
+        &mut self,
+        user_id: UserId,
+        until: Option<DateTime<Utc>>,
+        blocked_by: UserId,
+    ) {
+        self.unblock_user(user_id);
+        self.blocked_users.push(BlockedUser {
+            user_id,
+            until,
+            blocked_by,
+            created_at: Utc::now(),
+        });
+    }
+
+    pub(super) fn unblock_user(&mut self, user_id: UserId) -> bool {
+        // An expired mute is dropped as well, but it wasn't blocking the customer anymore
+        let was_active = self.active_block(user_id).is_some();
+        self.blocked_users.retain(|b| b.user_id != user_id);
+        was_active
+    }
+
+    pub(super) fn active_block(&self, user_id: UserId) -> Option<&BlockedUser> {
+        let now = Utc::now();
+        self.blocked_users
+            .iter()
+            .find(|b| b.user_id == user_id && b.is_active(now))
+    }
+
+    pub(super) fn relayed_copies(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> Vec<RelayedMessage> {
+        self.relayed_messages
+            .iter()
+            .filter(|r| r.source_chat_id == chat_id && r.source_message_id == message_id)
+            .cloned()
+            .collect()
+    }
+
+    pub(super) fn relayed_message_by_copy(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> Option<&RelayedMessage> {
+        self.relayed_messages
+            .iter()
+            .find(|r| r.copy_chat_id == chat_id && r.copy_message_id == message_id)
+    }
+}
+
+/// Storage backend keeping everything in memory, nothing survives a restart
+#[derive(Default)]
+pub struct MemoryStorage {
+    data: Mutex<MemoryData>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn create_ticket(&self, new: NewTicket) -> StorageResult<Ticket> {
+        self.data.lock().unwrap().create_ticket(new)
+    }
+
+    fn import_tickets(&self, tickets: Vec<NewTicket>) -> StorageResult<usize> {
+        Ok(self.data.lock().unwrap().import_tickets(tickets))
+    }
+
+    fn active_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .active_ticket_by_chat(chat_id)
+            .cloned())
+    }
+
+    fn active_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .active_ticket_by_thread(group_id, thread_id)
+            .cloned())
+    }
+
+    fn last_closed_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .last_closed_ticket_by_chat(chat_id)
+            .cloned())
+    }
+
+    fn last_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .last_ticket_by_thread(group_id, thread_id)
+            .cloned())
+    }
+
+    fn inactive_tickets(&self, before: DateTime<Utc>) -> StorageResult<Vec<Ticket>> {
+        Ok(self.data.lock().unwrap().inactive_tickets(before))
+    }
+
+    fn touch_ticket(&self, id: i64) -> StorageResult<()> {
+        self.data.lock().unwrap().touch_ticket(id);
+        Ok(())
+    }
+
+    fn mark_inactivity_warned(&self, id: i64) -> StorageResult<()> {
+        self.data.lock().unwrap().mark_inactivity_warned(id);
+        Ok(())
+    }
+
+    fn set_ticket_status(
+        &self,
+        id: i64,
+        status: TicketStatus,
+        close_reason: Option<CloseReason>,
+    ) -> StorageResult<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .set_ticket_status(id, status, close_reason);
+        Ok(())
+    }
+
+    fn assign_ticket(&self, id: i64, assignee: &StaffMember) -> StorageResult<()> {
+        self.data.lock().unwrap().assign_ticket(id, assignee);
+        Ok(())
+    }
+
+    fn tickets_assigned_to(&self, user_id: UserId) -> StorageResult<Vec<Ticket>> {
+        Ok(self.data.lock().unwrap().tickets_assigned_to(user_id))
+    }
+
+    fn save_staff_member(&self, member: &StaffMember) -> StorageResult<()> {
+        self.data.lock().unwrap().save_staff_member(member);
+        Ok(())
+    }
+
+    fn staff_member_by_username(&self, username: &str) -> StorageResult<Option<StaffMember>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .staff_member_by_username(username)
+            .cloned())
+    }
+
+    fn add_note(&self, note: NewNote) -> StorageResult<()> {
+        self.data.lock().unwrap().add_note(note);
+        Ok(())
+    }
+
+    fn save_relayed_message(&self, relayed: &RelayedMessage) -> StorageResult<()> {
+        self.data.lock().unwrap().save_relayed_message(relayed);
+        Ok(())
+    }
+
+    fn relayed_copies(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Vec<RelayedMessage>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .relayed_copies(chat_id, message_id))
+    }
+
+    fn relayed_message_by_copy(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Option<RelayedMessage>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .relayed_message_by_copy(chat_id, message_id)
+            .cloned())
+    }
+
+    fn set_ticket_language(&self, id: i64, language: Language) -> StorageResult<()> {
+        self.data.lock().unwrap().set_ticket_language(id, language);
+        Ok(())
+    }
+
+    fn user_profile(&self, user_id: UserId) -> StorageResult<Option<UserProfile>> {
+        Ok(self.data.lock().unwrap().user_profile(user_id))
+    }
+
+    fn save_user(&self, user_id: UserId, display_name: &str) -> StorageResult<()> {
+        self.data.lock().unwrap().save_user(user_id, display_name);
+        Ok(())
+    }
+
+    fn set_user_language(&self, user_id: UserId, language: Language) -> StorageResult<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .set_user_language(user_id, language);
+        Ok(())
+    }
+
+    fn set_user_team(&self, user_id: UserId, team: &str) -> StorageResult<()> {
+        self.data.lock().unwrap().set_user_team(user_id, team);
+        Ok(())
+    }
+
+    fn create_team(&self, name: &str, invite_code: &str) -> StorageResult<Team> {
+        self.data.lock().unwrap().create_team(name, invite_code)
+    }
+
+    fn team(&self, id: i64) -> StorageResult<Option<Team>> {
+        Ok(self.data.lock().unwrap().team(id).cloned())
+    }
+
+    fn team_by_name(&self, name: &str) -> StorageResult<Option<Team>> {
+        Ok(self.data.lock().unwrap().team_by_name(name).cloned())
+    }
+
+    fn team_by_invite_code(&self, invite_code: &str) -> StorageResult<Option<Team>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .team_by_invite_code(invite_code)
+            .cloned())
+    }
+
+    fn join_team(&self, user_id: UserId, team_id: i64) -> StorageResult<()> {
+        self.data.lock().unwrap().join_team(user_id, team_id);
+        Ok(())
+    }
+
+    fn team_members(&self, team_id: i64) -> StorageResult<Vec<UserId>> {
+        Ok(self.data.lock().unwrap().team_members(team_id))
+    }
+
+    fn active_tickets_by_team(&self, team_id: i64) -> StorageResult<Vec<Ticket>> {
+        Ok(self.data.lock().unwrap().active_tickets_by_team(team_id))
+    }
+
+    fn set_user_allowed(&self, user_id: UserId, allowed: bool) -> StorageResult<()> {
+        self.data.lock().unwrap().set_user_allowed(user_id, allowed);
+        Ok(())
+    }
+
+    fn set_team_allowed(&self, team_id: i64, allowed: bool) -> StorageResult<()> {
+        self.data.lock().unwrap().set_team_allowed(team_id, allowed);
+        Ok(())
+    }
+
+    fn allowed_users(&self) -> StorageResult<Vec<UserProfile>> {
+        Ok(self.data.lock().unwrap().allowed_users())
+    }
+
+    fn allowed_teams(&self) -> StorageResult<Vec<Team>> {
+        Ok(self.data.lock().unwrap().allowed_teams())
+    }
+
+    fn create_license(&self, code: &str) -> StorageResult<License> {
+        self.data.lock().unwrap().create_license(code)
+    }
+
+    fn redeem_license(&self, code: &str, user_id: UserId) -> StorageResult<Option<License>> {
+        Ok(self.data.lock().unwrap().redeem_license(code, user_id))
+    }
+
+    fn block_user(
+        &self,
+        user_id: UserId,
+        until: Option<DateTime<Utc>>,
+        blocked_by: UserId,
+    ) -> StorageResult<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .block_user(user_id, until, blocked_by);
+        Ok(())
+    }
+
+    fn unblock_user(&self, user_id: UserId) -> StorageResult<bool> {
+        Ok(self.data.lock().unwrap().unblock_user(user_id))
+    }
+
+    fn active_block(&self, user_id: UserId) -> StorageResult<Option<BlockedUser>> {
+        Ok(self.data.lock().unwrap().active_block(user_id).cloned())
+    }
+}
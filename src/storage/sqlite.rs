@@ -0,0 +1,844 @@
+// storage/sqlite.rs
+use super::{
+    BlockedUser, CloseReason, License, NewNote, NewTicket, RelayedMessage, StaffMember, Storage,
+    StorageError, StorageResult, Team, Ticket, TicketStatus, UserProfile,
+};
+use crate::config::config;
+use crate::state::{support_group_id, Language};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row};
+use std::path::Path;
+use std::sync::Mutex;
+use teloxide::types::{ChatId, MessageId, ThreadId, UserId};
+
+/// Schema migrations, applied in order. The index of the last applied one is kept in `user_version`.
+const MIGRATIONS: &[&str] = &[
+    // 1: tickets
+    "CREATE TABLE tickets (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        chat_id INTEGER NOT NULL,
+        thread_id INTEGER NOT NULL,
+        language TEXT NOT NULL,
+        ticket_type TEXT NOT NULL,
+        opened_by INTEGER NOT NULL,
+        opener_name TEXT NOT NULL,
+        status TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        closed_at TEXT
+    );
+    CREATE INDEX tickets_thread ON tickets (thread_id);
+    CREATE UNIQUE INDEX tickets_one_active_per_chat ON tickets (chat_id) WHERE status != 'closed';",
+    // 2: inactivity tracking and close reason
+    "ALTER TABLE tickets ADD COLUMN last_activity_at TEXT;
+    UPDATE tickets SET last_activity_at = updated_at;
+    ALTER TABLE tickets ADD COLUMN inactivity_warned_at TEXT;
+    ALTER TABLE tickets ADD COLUMN close_reason TEXT;
+    CREATE INDEX tickets_activity ON tickets (status, last_activity_at);",
+    // 3: staff members and ticket assignment
+    "CREATE TABLE staff (
+        user_id INTEGER PRIMARY KEY,
+        username TEXT COLLATE NOCASE,
+        name TEXT NOT NULL
+    );
+    CREATE INDEX staff_username ON staff (username);
+    ALTER TABLE tickets ADD COLUMN assigned_to INTEGER;
+    ALTER TABLE tickets ADD COLUMN assignee_name TEXT;
+    CREATE INDEX tickets_assigned_to ON tickets (assigned_to, status);",
+    // 4: internal notes
+    "CREATE TABLE notes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        ticket_id INTEGER NOT NULL REFERENCES tickets (id),
+        author_id INTEGER NOT NULL,
+        author_name TEXT NOT NULL,
+        text TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX notes_ticket ON notes (ticket_id);",
+    // 5: copies of the relayed messages
+    "CREATE TABLE relayed_messages (
+        source_chat_id INTEGER NOT NULL,
+        source_message_id INTEGER NOT NULL,
+        ticket_id INTEGER NOT NULL REFERENCES tickets (id),
+        copy_chat_id INTEGER NOT NULL,
+        copy_message_id INTEGER NOT NULL,
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (source_chat_id, source_message_id)
+    );",
+    // 6: lookup of the relayed messages by their copy
+    "CREATE INDEX relayed_messages_copy ON relayed_messages (copy_chat_id, copy_message_id);",
+    // 7: configurable categories instead of the ticket types, keeping the same ids
+    "ALTER TABLE tickets RENAME COLUMN ticket_type TO category;",
+    // 8: support group of the ticket, filled in with the default one on startup
+    "ALTER TABLE tickets ADD COLUMN group_id INTEGER;
+    CREATE INDEX tickets_group_thread ON tickets (group_id, thread_id);",
+    // 9: customer profiles
+    "CREATE TABLE users (
+        user_id INTEGER PRIMARY KEY,
+        language TEXT
+    );",
+    // 10: name, team and first contact of the customers
+    "ALTER TABLE users ADD COLUMN display_name TEXT NOT NULL DEFAULT '';
+    ALTER TABLE users ADD COLUMN team TEXT;
+    ALTER TABLE users ADD COLUMN first_contact_at TEXT;
+    UPDATE users SET first_contact_at = COALESCE(
+        (SELECT MIN(created_at) FROM tickets WHERE opened_by = users.user_id),
+        datetime('now')
+    );
+    CREATE INDEX tickets_opened_by ON tickets (opened_by);",
+    // 11: teams of customers sharing their tickets
+    "CREATE TABLE teams (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        invite_code TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL
+    );
+    ALTER TABLE users ADD COLUMN team_id INTEGER REFERENCES teams (id);
+    CREATE INDEX users_team ON users (team_id);
+    ALTER TABLE tickets ADD COLUMN team_id INTEGER REFERENCES teams (id);
+    ALTER TABLE tickets ADD COLUMN team_name TEXT;
+    CREATE INDEX tickets_team ON tickets (team_id, status);",
+    // 12: a copy of the relayed messages for each chat, since the team members get the messages of the support group too
+    "CREATE TABLE relayed_messages_by_chat (
+        source_chat_id INTEGER NOT NULL,
+        source_message_id INTEGER NOT NULL,
+        ticket_id INTEGER NOT NULL REFERENCES tickets (id),
+        copy_chat_id INTEGER NOT NULL,
+        copy_message_id INTEGER NOT NULL,
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (source_chat_id, source_message_id, copy_chat_id)
+    );
+    INSERT INTO relayed_messages_by_chat
+        SELECT source_chat_id, source_message_id, ticket_id, copy_chat_id, copy_message_id, created_at
+        FROM relayed_messages;
+    DROP TABLE relayed_messages;
+    ALTER TABLE relayed_messages_by_chat RENAME TO relayed_messages;
+    CREATE INDEX relayed_messages_copy ON relayed_messages (copy_chat_id, copy_message_id);",
+    // 13: allowlist of the customers and teams, and the license codes adding the customers to it
+    "ALTER TABLE users ADD COLUMN allowed INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE teams ADD COLUMN allowed INTEGER NOT NULL DEFAULT 0;
+    CREATE TABLE licenses (
+        code TEXT PRIMARY KEY,
+        created_at TEXT NOT NULL,
+        redeemed_by INTEGER,
+        redeemed_at TEXT
+    );",
+    // 14: customers banned or muted by the support group
+    "CREATE TABLE blocked_users (
+        user_id INTEGER PRIMARY KEY,
+        until TEXT,
+        blocked_by INTEGER NOT NULL,
+        created_at TEXT NOT NULL
+    );",
+    // 15: author of the relayed messages, the private chats being the ones of their author
+    "ALTER TABLE relayed_messages ADD COLUMN source_user_id INTEGER;
+    UPDATE relayed_messages SET source_user_id = source_chat_id WHERE source_chat_id > 0;",
+    // 16: copies holding several messages relayed together
+    "ALTER TABLE relayed_messages ADD COLUMN coalesced INTEGER NOT NULL DEFAULT 0;",
+];
+
+const TICKET_COLUMNS: &str = "id, chat_id, thread_id, language, category, opened_by, opener_name, status, created_at, updated_at, closed_at, last_activity_at, inactivity_warned_at, close_reason, assigned_to, assignee_name, group_id, team_id, team_name";
+
+fn ticket_from_row(row: &Row) -> rusqlite::Result<Ticket> {
+    let invalid = |index: usize, value: String| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            rusqlite::types::Type::Text,
+            format!("invalid value {:?}", value).into(),
+        )
+    };
+
+    let language: String = row.get(3)?;
+    let status: String = row.get(7)?;
+    let close_reason: Option<String> = row.get(13)?;
+
+    Ok(Ticket {
+        id: row.get(0)?,
+        chat_id: ChatId(row.get(1)?),
+        thread_id: ThreadId(MessageId(row.get(2)?)),
+        language: Language::from_code(&language).ok_or_else(|| invalid(3, language))?,
+        category: row.get(4)?,
+        opened_by: UserId(row.get(5)?),
+        opener_name: row.get(6)?,
+        status: TicketStatus::from_code(&status).ok_or_else(|| invalid(7, status))?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        closed_at: row.get(10)?,
+        last_activity_at: row.get(11)?,
+        inactivity_warned_at: row.get(12)?,
+        close_reason: close_reason
+            .map(|reason| CloseReason::from_code(&reason).ok_or_else(|| invalid(13, reason)))
+            .transpose()?,
+        assigned_to: row.get::<_, Option<u64>>(14)?.map(UserId),
+        assignee_name: row.get(15)?,
+        group_id: ChatId(row.get(16)?),
+        team_id: row.get(17)?,
+        team_name: row.get(18)?,
+    })
+}
+
+const RELAYED_MESSAGE_COLUMNS: &str =
+    "ticket_id, source_chat_id, source_message_id, copy_chat_id, copy_message_id, source_user_id, coalesced";
+
+fn relayed_message_from_row(row: &Row) -> rusqlite::Result<RelayedMessage> {
+    Ok(RelayedMessage {
+        ticket_id: row.get(0)?,
+        source_chat_id: ChatId(row.get(1)?),
+        source_message_id: MessageId(row.get(2)?),
+        copy_chat_id: ChatId(row.get(3)?),
+        copy_message_id: MessageId(row.get(4)?),
+        source_user_id: row.get::<_, Option<u64>>(5)?.map(UserId),
+        coalesced: row.get(6)?,
+    })
+}
+
+/// Storage backend on a SQLite database
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) the database at `path` and applies the pending migrations
+    pub fn open(path: impl AsRef<Path>) -> StorageResult<Self> {
+        let mut conn = Connection::open(path)?;
+        Self::migrate(&mut conn)?;
+
+        // Tickets saved before the routing live in the default support group
+        conn.execute(
+            "UPDATE tickets SET group_id = ?1 WHERE group_id IS NULL",
+            params![support_group_id().0],
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", index + 1)?;
+            tx.commit()?;
+            println!("Applied database migration {}", index + 1);
+        }
+
+        Ok(())
+    }
+}
+
+const TEAM_COLUMNS: &str = "id, name, invite_code, created_at, allowed";
+
+fn team_from_row(row: &Row) -> rusqlite::Result<Team> {
+    Ok(Team {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        invite_code: row.get(2)?,
+        created_at: row.get(3)?,
+        allowed: row.get(4)?,
+    })
+}
+
+const USER_COLUMNS: &str = "user_id, display_name, language, team, first_contact_at,
+    (SELECT COUNT(*) FROM tickets WHERE opened_by = users.user_id), team_id, allowed";
+
+fn user_from_row(row: &Row) -> rusqlite::Result<UserProfile> {
+    let language: Option<String> = row.get(2)?;
+    Ok(UserProfile {
+        user_id: UserId(row.get(0)?),
+        display_name: row.get(1)?,
+        // A language whose locale file was removed is forgotten
+        language: language.as_deref().and_then(Language::from_code),
+        team: row.get(3)?,
+        first_contact_at: row.get(4)?,
+        ticket_count: row.get(5)?,
+        team_id: row.get(6)?,
+        allowed: row.get(7)?,
+    })
+}
+
+/// Inserts an open ticket. With `or_ignore`, a ticket for a chat that already has an active one is skipped.
+fn insert_ticket(
+    conn: &Connection,
+    new: &NewTicket,
+    now: DateTime<Utc>,
+    or_ignore: bool,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        &format!(
+            "INSERT {}INTO tickets (chat_id, thread_id, language, category, opened_by, opener_name, status, created_at, updated_at, last_activity_at, group_id, team_id, team_name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?8, ?9, ?10, ?11)",
+            if or_ignore { "OR IGNORE " } else { "" }
+        ),
+        params![
+            new.chat_id.0,
+            new.thread_id.0 .0,
+            new.language.code(),
+            new.category,
+            new.opened_by.0,
+            new.opener_name,
+            TicketStatus::Open.code(),
+            now,
+            new.group_id.0,
+            new.team_id,
+            new.team_name,
+        ],
+    )
+}
+
+impl Storage for SqliteStorage {
+    fn create_ticket(&self, new: NewTicket) -> StorageResult<Ticket> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        match insert_ticket(&conn, &new, now, false) {
+            // The unique index allows a single active ticket per chat
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == ErrorCode::ConstraintViolation =>
+            {
+                return Err(StorageError::Conflict(format!(
+                    "chat {} already has an active ticket",
+                    new.chat_id
+                )));
+            }
+            result => result?,
+        };
+
+        Ok(Ticket::new_open(conn.last_insert_rowid(), new, now))
+    }
+
+    fn import_tickets(&self, tickets: Vec<NewTicket>) -> StorageResult<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+        let mut imported = 0;
+        for new in &tickets {
+            imported += insert_ticket(&tx, new, now, true)?;
+        }
+        tx.commit()?;
+        Ok(imported)
+    }
+
+    fn active_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!(
+                    "SELECT {TICKET_COLUMNS} FROM tickets WHERE chat_id = ?1 AND status != 'closed'"
+                ),
+                params![chat_id.0],
+                ticket_from_row,
+            )
+            .optional()?)
+    }
+
+    fn active_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!(
+                    "SELECT {TICKET_COLUMNS} FROM tickets WHERE group_id = ?1 AND thread_id = ?2 AND status != 'closed'"
+                ),
+                params![group_id.0, thread_id.0 .0],
+                ticket_from_row,
+            )
+            .optional()?)
+    }
+
+    fn last_closed_ticket_by_chat(&self, chat_id: ChatId) -> StorageResult<Option<Ticket>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!(
+                    "SELECT {TICKET_COLUMNS} FROM tickets WHERE chat_id = ?1 AND status = 'closed' ORDER BY closed_at DESC LIMIT 1"
+                ),
+                params![chat_id.0],
+                ticket_from_row,
+            )
+            .optional()?)
+    }
+
+    fn last_ticket_by_thread(
+        &self,
+        group_id: ChatId,
+        thread_id: ThreadId,
+    ) -> StorageResult<Option<Ticket>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!(
+                    "SELECT {TICKET_COLUMNS} FROM tickets WHERE group_id = ?1 AND thread_id = ?2 ORDER BY created_at DESC LIMIT 1"
+                ),
+                params![group_id.0, thread_id.0 .0],
+                ticket_from_row,
+            )
+            .optional()?)
+    }
+
+    fn inactive_tickets(&self, before: DateTime<Utc>) -> StorageResult<Vec<Ticket>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TICKET_COLUMNS} FROM tickets WHERE status != 'closed' AND last_activity_at < ?1"
+        ))?;
+        let tickets = stmt
+            .query_map(params![before], ticket_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tickets)
+    }
+
+    fn touch_ticket(&self, id: i64) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE tickets SET updated_at = ?2, last_activity_at = ?2, inactivity_warned_at = NULL WHERE id = ?1",
+            params![id, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn mark_inactivity_warned(&self, id: i64) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE tickets SET inactivity_warned_at = ?2 WHERE id = ?1",
+            params![id, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn set_ticket_status(
+        &self,
+        id: i64,
+        status: TicketStatus,
+        close_reason: Option<CloseReason>,
+    ) -> StorageResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+        tx.execute(
+            "UPDATE tickets SET status = ?2, updated_at = ?3, closed_at = ?4, close_reason = ?5 WHERE id = ?1",
+            params![
+                id,
+                status.code(),
+                now,
+                (status == TicketStatus::Closed).then_some(now),
+                close_reason.map(CloseReason::code),
+            ],
+        )?;
+        // The messages of the closed tickets are kept while they can be reopened
+        if status == TicketStatus::Closed {
+            tx.execute(
+                "DELETE FROM relayed_messages WHERE ticket_id IN (SELECT id FROM tickets WHERE status = 'closed' AND closed_at < ?1)",
+                params![now - config().reopen_window],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn assign_ticket(&self, id: i64, assignee: &StaffMember) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE tickets SET assigned_to = ?2, assignee_name = ?3, updated_at = ?4 WHERE id = ?1",
+            params![id, assignee.user_id.0, assignee.name, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn tickets_assigned_to(&self, user_id: UserId) -> StorageResult<Vec<Ticket>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TICKET_COLUMNS} FROM tickets WHERE assigned_to = ?1 AND status != 'closed' ORDER BY created_at"
+        ))?;
+        let tickets = stmt
+            .query_map(params![user_id.0], ticket_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tickets)
+    }
+
+    fn save_staff_member(&self, member: &StaffMember) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO staff (user_id, username, name) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET username = excluded.username, name = excluded.name",
+            params![member.user_id.0, member.username, member.name],
+        )?;
+        Ok(())
+    }
+
+    fn staff_member_by_username(&self, username: &str) -> StorageResult<Option<StaffMember>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT user_id, username, name FROM staff WHERE username = ?1",
+                params![username],
+                |row| {
+                    Ok(StaffMember {
+                        user_id: UserId(row.get(0)?),
+                        username: row.get(1)?,
+                        name: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    fn add_note(&self, note: NewNote) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO notes (ticket_id, author_id, author_name, text, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![note.ticket_id, note.author_id.0, note.author_name, note.text, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn save_relayed_message(&self, relayed: &RelayedMessage) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO relayed_messages (source_chat_id, source_message_id, ticket_id, copy_chat_id, copy_message_id, created_at, source_user_id, coalesced)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                relayed.source_chat_id.0,
+                relayed.source_message_id.0,
+                relayed.ticket_id,
+                relayed.copy_chat_id.0,
+                relayed.copy_message_id.0,
+                Utc::now(),
+                relayed.source_user_id.map(|user_id| user_id.0),
+                relayed.coalesced,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn relayed_copies(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Vec<RelayedMessage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RELAYED_MESSAGE_COLUMNS} FROM relayed_messages WHERE source_chat_id = ?1 AND source_message_id = ?2 ORDER BY created_at"
+        ))?;
+        let copies = stmt
+            .query_map(params![chat_id.0, message_id.0], relayed_message_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(copies)
+    }
+
+    fn relayed_message_by_copy(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+    ) -> StorageResult<Option<RelayedMessage>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!(
+                    "SELECT {RELAYED_MESSAGE_COLUMNS} FROM relayed_messages WHERE copy_chat_id = ?1 AND copy_message_id = ?2"
+                ),
+                params![chat_id.0, message_id.0],
+                relayed_message_from_row,
+            )
+            .optional()?)
+    }
+
+    fn set_ticket_language(&self, id: i64, language: Language) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE tickets SET language = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, language.code(), Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn user_profile(&self, user_id: UserId) -> StorageResult<Option<UserProfile>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!("SELECT {USER_COLUMNS} FROM users WHERE user_id = ?1"),
+                params![user_id.0],
+                user_from_row,
+            )
+            .optional()?)
+    }
+
+    fn save_user(&self, user_id: UserId, display_name: &str) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO users (user_id, display_name, first_contact_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET display_name = excluded.display_name",
+            params![user_id.0, display_name, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn set_user_language(&self, user_id: UserId, language: Language) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO users (user_id, language, first_contact_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET language = excluded.language",
+            params![user_id.0, language.code(), Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn set_user_team(&self, user_id: UserId, team: &str) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO users (user_id, team, first_contact_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET team = excluded.team",
+            params![user_id.0, team, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn create_team(&self, name: &str, invite_code: &str) -> StorageResult<Team> {
+        let conn = self.conn.lock().unwrap();
+        if conn
+            .query_row("SELECT 1 FROM teams WHERE name = ?1", params![name], |_| {
+                Ok(())
+            })
+            .optional()?
+            .is_some()
+        {
+            return Err(StorageError::Conflict(format!(
+                "team {} already exists",
+                name
+            )));
+        }
+        if conn
+            .query_row(
+                "SELECT 1 FROM teams WHERE invite_code = ?1",
+                params![invite_code],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some()
+        {
+            return Err(StorageError::Conflict(format!(
+                "invite code {} already used",
+                invite_code
+            )));
+        }
+
+        let now = Utc::now();
+        conn.execute(
+            "INSERT INTO teams (name, invite_code, created_at) VALUES (?1, ?2, ?3)",
+            params![name, invite_code, now],
+        )?;
+        Ok(Team {
+            id: conn.last_insert_rowid(),
+            name: name.to_string(),
+            invite_code: invite_code.to_string(),
+            created_at: now,
+            allowed: false,
+        })
+    }
+
+    fn team(&self, id: i64) -> StorageResult<Option<Team>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!("SELECT {TEAM_COLUMNS} FROM teams WHERE id = ?1"),
+                params![id],
+                team_from_row,
+            )
+            .optional()?)
+    }
+
+    fn team_by_name(&self, name: &str) -> StorageResult<Option<Team>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!("SELECT {TEAM_COLUMNS} FROM teams WHERE name = ?1"),
+                params![name],
+                team_from_row,
+            )
+            .optional()?)
+    }
+
+    fn team_by_invite_code(&self, invite_code: &str) -> StorageResult<Option<Team>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!("SELECT {TEAM_COLUMNS} FROM teams WHERE invite_code = ?1"),
+                params![invite_code],
+                team_from_row,
+            )
+            .optional()?)
+    }
+
+    fn join_team(&self, user_id: UserId, team_id: i64) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO users (user_id, team_id, first_contact_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET team_id = excluded.team_id",
+            params![user_id.0, team_id, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn team_members(&self, team_id: i64) -> StorageResult<Vec<UserId>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT user_id FROM users WHERE team_id = ?1")?;
+        let members = stmt
+            .query_map(params![team_id], |row| Ok(UserId(row.get(0)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(members)
+    }
+
+    fn active_tickets_by_team(&self, team_id: i64) -> StorageResult<Vec<Ticket>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TICKET_COLUMNS} FROM tickets WHERE team_id = ?1 AND status != 'closed' ORDER BY created_at"
+        ))?;
+        let tickets = stmt
+            .query_map(params![team_id], ticket_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tickets)
+    }
+
+    fn set_user_allowed(&self, user_id: UserId, allowed: bool) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO users (user_id, allowed, first_contact_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET allowed = excluded.allowed",
+            params![user_id.0, allowed, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn set_team_allowed(&self, team_id: i64, allowed: bool) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE teams SET allowed = ?2 WHERE id = ?1",
+            params![team_id, allowed],
+        )?;
+        Ok(())
+    }
+
+    fn allowed_users(&self) -> StorageResult<Vec<UserProfile>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {USER_COLUMNS} FROM users WHERE allowed ORDER BY display_name"
+        ))?;
+        let users = stmt
+            .query_map([], user_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(users)
+    }
+
+    fn allowed_teams(&self) -> StorageResult<Vec<Team>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TEAM_COLUMNS} FROM teams WHERE allowed ORDER BY name"
+        ))?;
+        let teams = stmt
+            .query_map([], team_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(teams)
+    }
+
+    fn create_license(&self, code: &str) -> StorageResult<License> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO licenses (code, created_at) VALUES (?1, ?2)",
+            params![code, now],
+        )?;
+        if inserted == 0 {
+            return Err(StorageError::Conflict(format!(
+                "license {} already exists",
+                code
+            )));
+        }
+
+        Ok(License {
+            code: code.to_string(),
+            created_at: now,
+            redeemed_by: None,
+            redeemed_at: None,
+        })
+    }
+
+    fn redeem_license(&self, code: &str, user_id: UserId) -> StorageResult<Option<License>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+        let license = tx
+            .query_row(
+                "UPDATE licenses SET redeemed_by = ?2, redeemed_at = ?3
+                 WHERE code = ?1 AND redeemed_by IS NULL
+                 RETURNING code, created_at, redeemed_by, redeemed_at",
+                params![code, user_id.0, now],
+                |row| {
+                    Ok(License {
+                        code: row.get(0)?,
+                        created_at: row.get(1)?,
+                        redeemed_by: row.get::<_, Option<u64>>(2)?.map(UserId),
+                        redeemed_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        if license.is_some() {
+            tx.execute(
+                "INSERT INTO users (user_id, allowed, first_contact_at) VALUES (?1, 1, ?2)
+                 ON CONFLICT (user_id) DO UPDATE SET allowed = 1",
+                params![user_id.0, now],
+            )?;
+        }
+        tx.commit()?;
+        Ok(license)
+    }
+
+    fn block_user(
+        &self,
+        user_id: UserId,
+        until: Option<DateTime<Utc>>,
+        blocked_by: UserId,
+    ) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO blocked_users (user_id, until, blocked_by, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![user_id.0, until, blocked_by.0, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    fn unblock_user(&self, user_id: UserId) -> StorageResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let active = conn.execute(
+            "DELETE FROM blocked_users WHERE user_id = ?1 AND (until IS NULL OR until > ?2)",
+            params![user_id.0, Utc::now()],
+        )?;
+        // An expired mute is dropped as well, but it wasn't blocking the customer anymore
+        conn.execute(
+            "DELETE FROM blocked_users WHERE user_id = ?1",
+            params![user_id.0],
+        )?;
+        Ok(active > 0)
+    }
+
+    fn active_block(&self, user_id: UserId) -> StorageResult<Option<BlockedUser>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT until, blocked_by, created_at FROM blocked_users
+                 WHERE user_id = ?1 AND (until IS NULL OR until > ?2)",
+                params![user_id.0, Utc::now()],
+                |row| {
+                    Ok(BlockedUser {
+                        user_id,
+                        until: row.get(0)?,
+                        blocked_by: UserId(row.get(1)?),
+                        created_at: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+}
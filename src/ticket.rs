@@ -0,0 +1,189 @@
+// ticket.rs
+use crate::category::category_name;
+use crate::state::{Language, StateContainer};
+use crate::storage::{CloseReason, StaffMember, Ticket, TicketStatus};
+use teloxide::prelude::*;
+
+/// Name of a ticket topic in the support group, prefixed with the ticket status and followed by the team of the
+/// customer and the assignee
+pub fn format_topic_name(
+    status: TicketStatus,
+    language: Language,
+    category: &str,
+    opener_name: &str,
+    team_name: Option<&str>,
+    assignee_name: Option<&str>,
+) -> String {
+    let mut name = format!(
+        "{} {} {} - {}",
+        status.topic_prefix(),
+        language.to_flag(),
+        category_name(category),
+        opener_name
+    );
+    if let Some(team) = team_name {
+        name = format!("{} ({})", name, team);
+    }
+    if let Some(assignee) = assignee_name {
+        name = format!("{} 👤 {}", name, assignee);
+    }
+    truncate_topic_name(name)
+}
+
+/// Longest topic name accepted by Telegram, in characters
+const MAX_TOPIC_NAME_LENGTH: usize = 128;
+
+/// Cuts the end of names too long for a topic, on a character boundary
+fn truncate_topic_name(mut name: String) -> String {
+    if let Some((end, _)) = name.char_indices().nth(MAX_TOPIC_NAME_LENGTH) {
+        name.truncate(end);
+    }
+    name
+}
+
+/// Name of the topic of `ticket`
+pub fn topic_name(ticket: &Ticket) -> String {
+    format_topic_name(
+        ticket.status,
+        ticket.language,
+        &ticket.category,
+        &ticket.opener_name,
+        ticket.team_name.as_deref(),
+        ticket.assignee_name.as_deref(),
+    )
+}
+
+/// Assigns the ticket to a member of the support group, showing them in the topic name
+pub async fn assign_ticket(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &mut Ticket,
+    assignee: &StaffMember,
+) {
+    if let Err(e) = state.storage.assign_ticket(ticket.id, assignee) {
+        eprintln!("Error assigning ticket #{}: {}", ticket.id, e);
+        return;
+    }
+
+    let old_name = topic_name(ticket);
+    ticket.assigned_to = Some(assignee.user_id);
+    ticket.assignee_name = Some(assignee.name.clone());
+    rename_topic(bot, ticket, &old_name).await;
+}
+
+/// Changes the language of the ticket, updating the flag in the topic name
+pub async fn set_ticket_language(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &mut Ticket,
+    language: Language,
+) {
+    if let Err(e) = state.storage.set_ticket_language(ticket.id, language) {
+        eprintln!("Error updating ticket #{}: {}", ticket.id, e);
+        return;
+    }
+
+    let old_name = topic_name(ticket);
+    ticket.language = language;
+    rename_topic(bot, ticket, &old_name).await;
+}
+
+/// Moves the ticket to `status`, saving it and updating its topic in the support group:
+/// the topic is renamed with the status prefix and closed or reopened when needed.
+pub async fn set_ticket_status(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &mut Ticket,
+    status: TicketStatus,
+) {
+    change_status(bot, state, ticket, status, None).await;
+}
+
+/// Closes the ticket for `reason`, keeping it in the history
+pub async fn close_ticket(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &mut Ticket,
+    reason: CloseReason,
+) {
+    change_status(bot, state, ticket, TicketStatus::Closed, Some(reason)).await;
+}
+
+async fn change_status(
+    bot: &Bot,
+    state: &StateContainer,
+    ticket: &mut Ticket,
+    status: TicketStatus,
+    close_reason: Option<CloseReason>,
+) {
+    if ticket.status == status {
+        return;
+    }
+
+    if let Err(e) = state
+        .storage
+        .set_ticket_status(ticket.id, status, close_reason)
+    {
+        eprintln!("Error updating the status of ticket #{}: {}", ticket.id, e);
+        return;
+    }
+
+    let was_closed = ticket.status.closes_topic();
+    let old_name = topic_name(ticket);
+    ticket.status = status;
+    ticket.close_reason = close_reason;
+
+    // Failing to update the topic must not stop the ticket, so errors are only logged
+    if was_closed && !status.closes_topic() {
+        if let Err(e) = bot
+            .reopen_forum_topic(ticket.group_id, ticket.thread_id)
+            .await
+        {
+            eprintln!("Error reopening the topic of ticket #{}: {}", ticket.id, e);
+        }
+    }
+
+    rename_topic(bot, ticket, &old_name).await;
+
+    if !was_closed && status.closes_topic() {
+        if let Err(e) = bot
+            .close_forum_topic(ticket.group_id, ticket.thread_id)
+            .await
+        {
+            eprintln!("Error closing the topic of ticket #{}: {}", ticket.id, e);
+        }
+    }
+}
+
+/// Updates the topic name after a change of the ticket, only logging errors.
+/// Nothing is sent when the name stays `old_name`, since every rename shows up in the topic.
+async fn rename_topic(bot: &Bot, ticket: &Ticket, old_name: &str) {
+    if topic_name(ticket) == old_name {
+        return;
+    }
+    if let Err(e) = bot
+        .edit_forum_topic(ticket.group_id, ticket.thread_id)
+        .name(topic_name(ticket))
+        .await
+    {
+        eprintln!("Error renaming the topic of ticket #{}: {}", ticket.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_long_topic_names() {
+        let short = "🟢 🇬🇧 Billing - mario".to_string();
+        assert_eq!(truncate_topic_name(short.clone()), short);
+
+        let long = truncate_topic_name("é".repeat(200));
+        assert_eq!(long, "é".repeat(MAX_TOPIC_NAME_LENGTH));
+
+        let emoji = truncate_topic_name(format!("a{}", "👤".repeat(200)));
+        assert_eq!(emoji.chars().count(), MAX_TOPIC_NAME_LENGTH);
+        assert!(emoji.starts_with('a') && emoji.ends_with('👤'));
+    }
+}
@@ -1,8 +1,12 @@
+use chrono::TimeDelta;
 use rand::seq::IteratorRandom;
 use teloxide::types::{Rgb, User};
 
+/// Topic icon colors allowed by Telegram
+pub const TOPIC_COLORS: [u32; 6] = [0x6FB9F0, 0xFFD67E, 0xCB86DB, 0x8EEE98, 0xFF93B2, 0xFB6F5F];
+
 pub fn get_random_topic_color() -> Rgb {
-    let rnd = vec![0x6FB9F0, 0xFFD67E, 0xCB86DB, 0x8EEE98, 0xFF93B2, 0xFB6F5F]
+    let rnd = TOPIC_COLORS
         .into_iter()
         .choose(&mut rand::thread_rng())
         .unwrap();
@@ -10,6 +14,67 @@ pub fn get_random_topic_color() -> Rgb {
     Rgb::from_u32(rnd)
 }
 
+/// Characters of the team invite codes and of the license codes, without the ones easy to mistake for each other (0 and O, 1 and I)
+const INVITE_CODE_CHARS: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+pub fn generate_invite_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..8)
+        .map(|_| INVITE_CODE_CHARS.chars().choose(&mut rng).unwrap())
+        .collect()
+}
+
+/// Longest duration accepted by `parse_duration`
+const MAX_DURATION: TimeDelta = TimeDelta::days(366);
+
+/// Parses a duration written as a number followed by its unit: `30m`, `2h`, `1d` or `1w`, up to a year
+pub fn parse_duration(text: &str) -> Option<TimeDelta> {
+    let text = text.trim();
+    let unit_start = text.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = text.split_at(unit_start);
+    let amount = amount.parse().ok().filter(|&amount| amount > 0)?;
+
+    let duration = match unit.trim().to_lowercase().as_str() {
+        "m" | "min" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => None,
+    };
+    duration.filter(|&duration| duration <= MAX_DURATION)
+}
+
 pub fn get_user_name(user: &User) -> String {
     user.username.clone().unwrap_or(user.first_name.clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30m"), Some(TimeDelta::minutes(30)));
+        assert_eq!(parse_duration(" 2H "), Some(TimeDelta::hours(2)));
+        assert_eq!(parse_duration("1d"), Some(TimeDelta::days(1)));
+        assert_eq!(parse_duration("1w"), Some(TimeDelta::weeks(1)));
+        assert_eq!(parse_duration("52w"), Some(TimeDelta::weeks(52)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        // Overflowing, or too long to add to the current date
+        assert_eq!(parse_duration("99999999w"), None);
+        assert_eq!(parse_duration("99999999999999999999m"), None);
+        assert_eq!(parse_duration("2000d"), None);
+        // Zero
+        assert_eq!(parse_duration("0m"), None);
+        // Missing amount or unit
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration(""), None);
+        // Unknown unit
+        assert_eq!(parse_duration("5y"), None);
+        assert_eq!(parse_duration("5 parsecs"), None);
+    }
+}